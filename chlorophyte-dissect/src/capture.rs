@@ -0,0 +1,204 @@
+//! Reads the frames out of pcap and pcapng files
+
+use log::warn;
+use std::io;
+use std::io::ErrorKind;
+
+/// A link layer frame as it was captured
+pub struct Frame {
+    /// Seconds since the Unix epoch, 0 if the capture doesn't say
+    pub timestamp: f64,
+    /// The `LINKTYPE_` of the interface the frame was captured on
+    pub link_type: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn u16(self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        match self {
+            Self::Little => u16::from_le_bytes(bytes),
+            Self::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    fn u32(self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match self {
+            Self::Little => u32::from_le_bytes(bytes),
+            Self::Big => u32::from_be_bytes(bytes),
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+/// Reads every frame of a pcap or pcapng file.
+/// A capture that was cut off in the middle of a frame is read up to that frame
+pub fn read_frames(file: &[u8]) -> io::Result<Vec<Frame>> {
+    match file.get(..4) {
+        Some([0x0A, 0x0D, 0x0D, 0x0A]) => read_pcapng(file),
+        Some(_) => read_pcap(file),
+        None => Err(invalid("File is too short to be a capture")),
+    }
+}
+
+fn read_pcap(file: &[u8]) -> io::Result<Vec<Frame>> {
+    let header = file
+        .get(..24)
+        .ok_or_else(|| invalid("Truncated pcap header"))?;
+    // The magic number tells the byte order and whether timestamps are in µs or ns
+    let (order, fractions_per_second) = match header[..4] {
+        [0xD4, 0xC3, 0xB2, 0xA1] => (ByteOrder::Little, 1e6),
+        [0xA1, 0xB2, 0xC3, 0xD4] => (ByteOrder::Big, 1e6),
+        [0x4D, 0x3C, 0xB2, 0xA1] => (ByteOrder::Little, 1e9),
+        [0xA1, 0xB2, 0x3C, 0x4D] => (ByteOrder::Big, 1e9),
+        _ => return Err(invalid("Not a pcap or pcapng file")),
+    };
+    // The upper bits can hold FCS information
+    let link_type = order.u32(&header[20..]) & 0x0FFF_FFFF;
+
+    let mut frames = vec![];
+    let mut rest = &file[24..];
+    while !rest.is_empty() {
+        let Some(record) = rest.get(..16) else {
+            warn!("The capture ends in the middle of a record header");
+            break;
+        };
+        let seconds = order.u32(record);
+        let fraction = order.u32(&record[4..]);
+        let len = order.u32(&record[8..]) as usize;
+        let Some(data) = rest.get(16..16 + len) else {
+            warn!("The capture ends in the middle of a frame");
+            break;
+        };
+        frames.push(Frame {
+            timestamp: f64::from(seconds) + f64::from(fraction) / fractions_per_second,
+            link_type,
+            data: data.to_vec(),
+        });
+        rest = &rest[16 + len..];
+    }
+    Ok(frames)
+}
+
+/// An interface declared by an Interface Description Block
+struct Interface {
+    link_type: u32,
+    /// What a timestamp of 1 means
+    units_per_second: f64,
+}
+
+fn read_pcapng(file: &[u8]) -> io::Result<Vec<Frame>> {
+    let mut order = ByteOrder::Little;
+    let mut interfaces = vec![];
+    let mut frames = vec![];
+    let mut rest = file;
+    while rest.len() >= 12 {
+        // Section Header Block, which sets the byte order of the blocks after it
+        if rest[..4] == [0x0A, 0x0D, 0x0D, 0x0A] {
+            order = match rest[8..12] {
+                [0x4D, 0x3C, 0x2B, 0x1A] => ByteOrder::Little,
+                [0x1A, 0x2B, 0x3C, 0x4D] => ByteOrder::Big,
+                _ => return Err(invalid("Invalid pcapng byte order magic")),
+            };
+            interfaces.clear();
+        }
+        let block_type = order.u32(rest);
+        let len = order.u32(&rest[4..]) as usize;
+        if len < 12 || !len.is_multiple_of(4) {
+            return Err(invalid("Invalid pcapng block length"));
+        }
+        let Some(block) = rest.get(..len) else {
+            warn!("The capture ends in the middle of a block");
+            break;
+        };
+        let body = &block[8..len - 4];
+        match block_type {
+            // Interface Description Block
+            1 => {
+                let header = body
+                    .get(..8)
+                    .ok_or_else(|| invalid("Truncated interface description block"))?;
+                interfaces.push(Interface {
+                    link_type: u32::from(order.u16(header)),
+                    units_per_second: units_per_second(order, &body[8..]),
+                });
+            }
+            // Enhanced Packet Block
+            6 => {
+                let header = body
+                    .get(..20)
+                    .ok_or_else(|| invalid("Truncated enhanced packet block"))?;
+                let interface = interfaces
+                    .get(order.u32(header) as usize)
+                    .ok_or_else(|| invalid("Packet captured on an undeclared interface"))?;
+                let timestamp =
+                    (u64::from(order.u32(&header[4..])) << 32) | u64::from(order.u32(&header[8..]));
+                // Timestamps are printed in µs, far from where f64 loses precision
+                #[allow(clippy::cast_precision_loss)]
+                let timestamp = timestamp as f64 / interface.units_per_second;
+                let captured = order.u32(&header[12..]) as usize;
+                let data = body
+                    .get(20..20 + captured)
+                    .ok_or_else(|| invalid("Truncated enhanced packet block"))?;
+                frames.push(Frame {
+                    timestamp,
+                    link_type: interface.link_type,
+                    data: data.to_vec(),
+                });
+            }
+            // Simple Packet Block, always captured on the first interface and without timestamp
+            3 => {
+                let interface = interfaces
+                    .first()
+                    .ok_or_else(|| invalid("Packet captured on an undeclared interface"))?;
+                let original = body
+                    .get(..4)
+                    .map(|len| order.u32(len) as usize)
+                    .ok_or_else(|| invalid("Truncated simple packet block"))?;
+                let data = &body[4..];
+                frames.push(Frame {
+                    timestamp: 0.0,
+                    link_type: interface.link_type,
+                    data: data[..original.min(data.len())].to_vec(),
+                });
+            }
+            _ => {}
+        }
+        rest = &rest[len..];
+    }
+    Ok(frames)
+}
+
+/// Reads the `if_tsresol` option of an interface, microseconds if there is none
+fn units_per_second(order: ByteOrder, mut options: &[u8]) -> f64 {
+    while options.len() >= 4 {
+        let code = order.u16(options);
+        let len = usize::from(order.u16(&options[2..]));
+        let Some(value) = options.get(4..4 + len) else {
+            break;
+        };
+        match (code, value) {
+            (0, _) => break,
+            // The most significant bit says whether it's a power of 2 or of 10
+            (9, [resolution]) if resolution & 0x80 != 0 => {
+                return 2f64.powi(i32::from(resolution & 0x7F));
+            }
+            (9, [resolution]) => return 10f64.powi(i32::from(*resolution)),
+            _ => {}
+        }
+        options = options
+            .get(4 + len.next_multiple_of(4)..)
+            .unwrap_or_default();
+    }
+    1e6
+}
@@ -0,0 +1,6 @@
+//! Reads pcap and pcapng captures and puts the TCP streams in them back together,
+//! so the Terraria packets they carry can be decoded
+
+pub mod capture;
+pub mod net;
+pub mod stream;
@@ -0,0 +1,119 @@
+#![warn(clippy::all, clippy::nursery, clippy::pedantic)]
+
+mod print;
+
+use chlorophyte_dissect::stream::Stream;
+use chlorophyte_dissect::{capture, net};
+use chlorophyte_terraria_protocol::packet::{C2SPacket, S2CPacket};
+use log::{warn, Level};
+use std::collections::{HashMap, HashSet};
+use std::env::{args, var};
+use std::fmt::Debug;
+use std::fs::read;
+use std::net::SocketAddr;
+
+/// How many characters of the fields of a packet are printed, unless `--full` is given
+const MAX_FIELDS_LEN: usize = 1000;
+/// Where the hex dumps and fields are printed, below the packet line
+const INDENT: &str = "            ";
+
+fn main() {
+    if var("RUST_LOG").is_err() {
+        simple_logger::init_with_level(Level::Info).unwrap();
+    } else {
+        simple_logger::init_with_env().unwrap();
+    }
+    let mut positional = vec![];
+    let mut ports = HashSet::new();
+    let mut full = false;
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                let port = args.next().expect("No port specified for --port");
+                ports.insert(port.parse::<u16>().expect("Failed to parse port as u16"));
+            }
+            "--full" => full = true,
+            flag if flag.starts_with("--") => panic!("Unknown flag: {flag}"),
+            _ => positional.push(arg),
+        }
+    }
+    let Some(capture_file) = positional.into_iter().next() else {
+        panic!("No capture file specified");
+    };
+    if ports.is_empty() {
+        ports.insert(7777);
+    }
+
+    let capture = read(capture_file).expect("Failed to read capture file");
+    let frames = capture::read_frames(&capture).expect("Failed to read capture");
+    let first_timestamp = frames.first().map_or(0.0, |f| f.timestamp);
+    let mut streams = HashMap::<(SocketAddr, SocketAddr), Stream>::new();
+    for frame in frames {
+        let Some(segment) = net::parse_frame(frame.link_type, &frame.data) else {
+            continue;
+        };
+        // Packets sent to the server port come from the client
+        let to_server = ports.contains(&segment.dst.port());
+        if !to_server && !ports.contains(&segment.src.port()) {
+            continue;
+        }
+        let (src, dst) = (segment.src, segment.dst);
+        let stream = streams.entry((src, dst)).or_default();
+        stream.push(segment.seq, segment.flags, segment.payload);
+        loop {
+            let header = format!(
+                "{:>10.6} {src} -> {dst} {}",
+                frame.timestamp - first_timestamp,
+                if to_server { "C2S" } else { "S2C" }
+            );
+            match stream.next_frame() {
+                Ok(Some((id, body))) => print_packet(&header, id, body, to_server, full),
+                Ok(None) => break,
+                Err(e) => println!("{header}     The stream can't be decoded any further: {e}"),
+            }
+        }
+    }
+
+    for ((src, dst), stream) in streams {
+        if stream.leftover() > 0 {
+            warn!(
+                "{src} -> {dst}: {} bytes were left over, the capture is missing segments or ends in the middle of a packet",
+                stream.leftover()
+            );
+        }
+    }
+}
+
+fn print_packet(header: &str, id: u8, body: Vec<u8>, to_server: bool, full: bool) {
+    let dump = print::hex_dump(&body, INDENT);
+    let len = body.len();
+    let fields = |name: &str, fields: &dyn Debug| {
+        let fields = format!("{fields:?}");
+        let fields = if full {
+            fields
+        } else {
+            print::truncate(fields, MAX_FIELDS_LEN)
+        };
+        println!("{header} {id:>3} {name}\n{INDENT}{fields}");
+    };
+    let unknown = || print!("{header} {id:>3} Unknown ({len} bytes)\n{dump}");
+    let failed = |e| print!("{header} {id:>3} Failed to decode ({len} bytes): {e}\n{dump}");
+    if to_server {
+        match C2SPacket::deserialize(id, body) {
+            Ok(packet) => match print::describe_c2s(&packet) {
+                Some((name, packet)) => fields(name, packet),
+                None => unknown(),
+            },
+            Err(e) => failed(e),
+        }
+    } else {
+        match S2CPacket::deserialize(id, body) {
+            Ok(packet) => match print::describe_s2c(&packet) {
+                Some((name, packet)) => fields(name, packet),
+                None => unknown(),
+            },
+            Err(e) => failed(e),
+        }
+    }
+}
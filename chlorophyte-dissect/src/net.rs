@@ -0,0 +1,105 @@
+//! Finds the TCP segments in captured frames
+
+use pnet_packet::ethernet::{EtherType, EtherTypes, EthernetPacket};
+use pnet_packet::ip::IpNextHeaderProtocols;
+use pnet_packet::ipv4::{Ipv4Flags, Ipv4Packet};
+use pnet_packet::ipv6::Ipv6Packet;
+use pnet_packet::tcp::TcpPacket;
+use pnet_packet::Packet;
+use std::net::{IpAddr, SocketAddr};
+
+/// A TCP segment that was found in a frame
+pub struct Segment {
+    pub src: SocketAddr,
+    pub dst: SocketAddr,
+    pub seq: u32,
+    /// [`pnet_packet::tcp::TcpFlags`]
+    pub flags: u8,
+    pub payload: Vec<u8>,
+}
+
+/// Takes the TCP segment out of a frame. Returns `None` for anything else, and for fragmented
+/// IPv4 packets
+pub fn parse_frame(link_type: u32, frame: &[u8]) -> Option<Segment> {
+    let (ethertype, ip) = match link_type {
+        // BSD loopback, with the address family in the byte order of the capturing host
+        0 => {
+            let family = u32::from_le_bytes(frame.get(..4)?.try_into().ok()?);
+            let ethertype = match family.min(family.swap_bytes()) {
+                2 => EtherTypes::Ipv4,
+                24 | 28 | 30 => EtherTypes::Ipv6,
+                _ => return None,
+            };
+            (ethertype, &frame[4..])
+        }
+        // Ethernet, maybe with VLAN tags
+        1 => {
+            let ethernet = EthernetPacket::new(frame)?;
+            let mut ethertype = ethernet.get_ethertype();
+            let mut payload = &frame[EthernetPacket::minimum_packet_size()..];
+            while ethertype == EtherTypes::Vlan || ethertype == EtherTypes::QinQ {
+                ethertype = EtherType(u16::from_be_bytes(payload.get(2..4)?.try_into().ok()?));
+                payload = &payload[4..];
+            }
+            (ethertype, payload)
+        }
+        // Raw IP
+        12 | 14 | 101 => {
+            let ethertype = match frame.first()? >> 4 {
+                4 => EtherTypes::Ipv4,
+                6 => EtherTypes::Ipv6,
+                _ => return None,
+            };
+            (ethertype, frame)
+        }
+        228 => (EtherTypes::Ipv4, frame),
+        229 => (EtherTypes::Ipv6, frame),
+        // Linux cooked capture, what `tcpdump -i any` writes
+        113 => (
+            EtherType(u16::from_be_bytes(frame.get(14..16)?.try_into().ok()?)),
+            frame.get(16..)?,
+        ),
+        276 => (
+            EtherType(u16::from_be_bytes(frame.get(..2)?.try_into().ok()?)),
+            frame.get(20..)?,
+        ),
+        _ => return None,
+    };
+
+    if ethertype == EtherTypes::Ipv4 {
+        let ip = Ipv4Packet::new(ip)?;
+        let fragmented =
+            ip.get_fragment_offset() != 0 || ip.get_flags() & Ipv4Flags::MoreFragments != 0;
+        if ip.get_next_level_protocol() != IpNextHeaderProtocols::Tcp || fragmented {
+            return None;
+        }
+        parse_tcp(
+            ip.get_source().into(),
+            ip.get_destination().into(),
+            ip.payload(),
+        )
+    } else if ethertype == EtherTypes::Ipv6 {
+        let ip = Ipv6Packet::new(ip)?;
+        if ip.get_next_header() != IpNextHeaderProtocols::Tcp {
+            return None;
+        }
+        parse_tcp(
+            ip.get_source().into(),
+            ip.get_destination().into(),
+            ip.payload(),
+        )
+    } else {
+        None
+    }
+}
+
+fn parse_tcp(src: IpAddr, dst: IpAddr, segment: &[u8]) -> Option<Segment> {
+    let tcp = TcpPacket::new(segment)?;
+    Some(Segment {
+        src: SocketAddr::new(src, tcp.get_source()),
+        dst: SocketAddr::new(dst, tcp.get_destination()),
+        seq: tcp.get_sequence(),
+        flags: tcp.get_flags(),
+        payload: tcp.payload().to_vec(),
+    })
+}
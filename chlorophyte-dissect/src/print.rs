@@ -0,0 +1,101 @@
+use chlorophyte_terraria_protocol::packet::{C2SPacket, S2CPacket};
+use std::fmt::{Debug, Write};
+
+/// Gives the name of a packet variant along with the packet inside it,
+/// `None` for packets the protocol crate doesn't know
+macro_rules! describe {
+    ($packet:expr, $enum:ident { $($variant:ident),* $(,)? }) => {
+        match $packet {
+            $($enum::$variant(p) => Some((stringify!($variant), p as &dyn Debug)),)*
+            $enum::Unknown { .. } => None,
+        }
+    };
+}
+
+pub fn describe_s2c(packet: &S2CPacket) -> Option<(&'static str, &dyn Debug)> {
+    describe!(
+        packet,
+        S2CPacket {
+            FatalError,
+            ConnectionApproved,
+            PlayerInfo,
+            WorldInfo,
+            StatusText,
+            SendSection,
+            TileFrameSection,
+            PlayerSpawn,
+            PlayerActive,
+            PlayerHealth,
+            SyncItem,
+            PasswordRequired,
+            PlayerMana,
+            CompleteConnectionAndSpawn,
+            PlayerBuffs,
+            TileCounts,
+            AnglerQuest,
+            NetModule,
+            KillCount,
+            TowerShields,
+            MoonLordCountdown,
+            FinishedConnectingToServer,
+            SetCountsAsHost,
+            SyncMods,
+        }
+    )
+}
+
+pub fn describe_c2s(packet: &C2SPacket) -> Option<(&'static str, &dyn Debug)> {
+    describe!(
+        packet,
+        C2SPacket {
+            Connect,
+            PlayerAppearance,
+            SetInvSlot,
+            RequestWorldInfo,
+            RequestInitialTileData,
+            SpawnPlayer,
+            SetHealth,
+            SendPassword,
+            SetMana,
+            SetBuffs,
+            ClientUuid,
+            NetMessageCommand,
+        }
+    )
+}
+
+/// Formats bytes as offset, 16 bytes of hex and their ASCII, one line per 16 bytes
+pub fn hex_dump(bytes: &[u8], indent: &str) -> String {
+    let mut dump = String::new();
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let hex = line
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let ascii = line
+            .iter()
+            .map(|b| {
+                if b.is_ascii_graphic() || *b == b' ' {
+                    char::from(*b)
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        writeln!(dump, "{indent}{:04x}  {hex:<47}  {ascii}", i * 16).unwrap();
+    }
+    dump
+}
+
+/// Cuts the text off after `max` characters
+pub fn truncate(text: String, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!(
+            "{}... ({} more characters)",
+            &text[..end],
+            text[end..].chars().count()
+        ),
+        None => text,
+    }
+}
@@ -0,0 +1,67 @@
+use chlorophyte_terraria_protocol::error::ProtocolError;
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use pnet_packet::tcp::TcpFlags;
+use std::collections::BTreeMap;
+
+/// One direction of a TCP connection, put back in order
+#[derive(Default)]
+pub struct Stream {
+    /// The sequence number of the first byte of the stream
+    start: Option<u32>,
+    /// How many bytes of the stream were passed to the decoder, so where the next segment starts
+    next: u32,
+    /// Segments that arrived before the ones in front of them, by their offset in the stream
+    pending: BTreeMap<u32, Vec<u8>>,
+    decoder: TerrariaFrameDecoder,
+}
+
+impl Stream {
+    /// Adds a segment. Retransmitted bytes are dropped, segments that arrived early are held
+    /// back until the gap before them is filled
+    pub fn push(&mut self, seq: u32, flags: u8, payload: Vec<u8>) {
+        if flags & TcpFlags::SYN != 0 {
+            self.start = Some(seq.wrapping_add(1));
+            return;
+        }
+        // Without the handshake, the stream starts at the first segment we see
+        let start = *self.start.get_or_insert(seq);
+        if payload.is_empty() {
+            return;
+        }
+        let offset = seq.wrapping_sub(start);
+        // Retransmissions of what came before the first segment we saw
+        if offset > u32::MAX / 2 {
+            return;
+        }
+        let pending = self.pending.entry(offset).or_default();
+        if payload.len() > pending.len() {
+            *pending = payload;
+        }
+
+        while let Some(entry) = self.pending.first_entry() {
+            let offset = *entry.key();
+            if offset > self.next {
+                break;
+            }
+            let payload = entry.remove();
+            // A TCP segment is never longer than 64 KiB
+            #[allow(clippy::cast_possible_truncation)]
+            let end = offset + payload.len() as u32;
+            if end > self.next {
+                self.decoder.feed(&payload[(self.next - offset) as usize..]);
+                self.next = end;
+            }
+        }
+    }
+
+    /// Takes the next complete packet out of the stream
+    pub fn next_frame(&mut self) -> Result<Option<(u8, Vec<u8>)>, ProtocolError> {
+        self.decoder.next_frame()
+    }
+
+    /// Bytes that were captured but can't be decoded: an incomplete packet at the end and
+    /// segments after a gap
+    pub fn leftover(&self) -> usize {
+        self.decoder.buffered() + self.pending.values().map(Vec::len).sum::<usize>()
+    }
+}
@@ -0,0 +1,114 @@
+use chlorophyte_dissect::capture::read_frames;
+
+fn assert_time(timestamp: f64, expected: f64) {
+    assert!(
+        (timestamp - expected).abs() < 1e-9,
+        "{timestamp} != {expected}"
+    );
+}
+
+#[test]
+fn pcap_little_endian_microseconds() {
+    #[rustfmt::skip]
+    let file = [
+        // Magic, version 2.4, time zone, accuracy, snapshot length, Ethernet
+        0xD4, 0xC3, 0xB2, 0xA1, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0, 0, 1, 0, 0, 0,
+        // 10 s and 500000 µs, 3 bytes captured of 3
+        10, 0, 0, 0, 0x20, 0xA1, 0x07, 0, 3, 0, 0, 0, 3, 0, 0, 0,
+        1, 2, 3,
+        // 11 s, 1 byte captured of 60
+        11, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 60, 0, 0, 0,
+        4,
+    ];
+    let frames = read_frames(&file).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_time(frames[0].timestamp, 10.5);
+    assert_eq!(frames[0].link_type, 1);
+    assert_eq!(frames[0].data, [1, 2, 3]);
+    assert_time(frames[1].timestamp, 11.0);
+    assert_eq!(frames[1].data, [4]);
+}
+
+#[test]
+fn pcap_big_endian_nanoseconds_cut_off() {
+    #[rustfmt::skip]
+    let file = [
+        // Magic, version 2.4, time zone, accuracy, snapshot length, Ethernet with FCS bits set
+        0xA1, 0xB2, 0x3C, 0x4D, 0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0x10, 0, 0, 1,
+        // 1 s and 250000000 ns, 2 bytes captured of 2
+        0, 0, 0, 1, 0x0E, 0xE6, 0xB2, 0x80, 0, 0, 0, 2, 0, 0, 0, 2,
+        9, 9,
+        // A record of 4 bytes that ends after the first
+        0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 4,
+        9,
+    ];
+    let frames = read_frames(&file).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_time(frames[0].timestamp, 1.25);
+    assert_eq!(frames[0].link_type, 1);
+    assert_eq!(frames[0].data, [9, 9]);
+}
+
+#[test]
+fn pcapng_blocks() {
+    #[rustfmt::skip]
+    let file = [
+        // Section Header Block, little endian, version 1.0, unknown section length
+        0x0A, 0x0D, 0x0D, 0x0A, 28, 0, 0, 0, 0x4D, 0x3C, 0x2B, 0x1A, 1, 0, 0, 0,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 28, 0, 0, 0,
+        // Interface Description Block, BSD loopback, if_tsresol of ns, end of options
+        1, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0, 0,
+        9, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0,
+        // Interface Statistics Block, which is skipped
+        5, 0, 0, 0, 12, 0, 0, 0, 12, 0, 0, 0,
+        // Enhanced Packet Block on interface 0 at 1500000000 ns, 3 bytes captured of 3
+        6, 0, 0, 0, 36, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x00, 0x2F, 0x68, 0x59,
+        3, 0, 0, 0, 3, 0, 0, 0, 1, 2, 3, 0, 36, 0, 0, 0,
+        // Simple Packet Block of 2 bytes
+        3, 0, 0, 0, 20, 0, 0, 0, 2, 0, 0, 0, 4, 5, 0, 0, 20, 0, 0, 0,
+        // An Enhanced Packet Block that ends in the middle
+        6, 0, 0, 0, 36, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    let frames = read_frames(&file).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_time(frames[0].timestamp, 1.5);
+    assert_eq!(frames[0].link_type, 0);
+    assert_eq!(frames[0].data, [1, 2, 3]);
+    assert_eq!(frames[1].link_type, 0);
+    assert_eq!(frames[1].data, [4, 5]);
+}
+
+#[test]
+fn pcapng_big_endian_without_resolution() {
+    #[rustfmt::skip]
+    let file = [
+        // Section Header Block, big endian
+        0x0A, 0x0D, 0x0D, 0x0A, 0, 0, 0, 28, 0x1A, 0x2B, 0x3C, 0x4D, 0, 1, 0, 0,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 28,
+        // Interface Description Block, Ethernet, no options so timestamps are in µs
+        0, 0, 0, 1, 0, 0, 0, 20, 0, 1, 0, 0, 0, 0, 0xFF, 0xFF, 0, 0, 0, 20,
+        // Enhanced Packet Block on interface 0 at 2000000 µs, 1 byte captured of 1
+        0, 0, 0, 6, 0, 0, 0, 36, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x1E, 0x84, 0x80,
+        0, 0, 0, 1, 0, 0, 0, 1, 7, 0, 0, 0, 0, 0, 0, 36,
+    ];
+    let frames = read_frames(&file).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_time(frames[0].timestamp, 2.0);
+    assert_eq!(frames[0].link_type, 1);
+    assert_eq!(frames[0].data, [7]);
+}
+
+#[test]
+fn invalid_captures() {
+    assert!(read_frames(&[0xD4, 0xC3]).is_err());
+    assert!(read_frames(&[0; 24]).is_err());
+    #[rustfmt::skip]
+    let undeclared_interface = [
+        0x0A, 0x0D, 0x0D, 0x0A, 28, 0, 0, 0, 0x4D, 0x3C, 0x2B, 0x1A, 1, 0, 0, 0,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 28, 0, 0, 0,
+        // Enhanced Packet Block on interface 0, which was never described
+        6, 0, 0, 0, 32, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0,
+    ];
+    assert!(read_frames(&undeclared_interface).is_err());
+}
@@ -0,0 +1,118 @@
+use chlorophyte_dissect::stream::Stream;
+use pnet_packet::tcp::TcpFlags;
+
+/// A Terraria packet with its length prefix
+fn packet(id: u8, body: &[u8]) -> Vec<u8> {
+    let len = u16::try_from(body.len() + 3).unwrap();
+    let mut packet = len.to_le_bytes().to_vec();
+    packet.push(id);
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// Two packets back to back, 12 bytes
+fn two_packets() -> Vec<u8> {
+    [packet(1, &[1, 2, 3, 4]), packet(2, &[5, 6])].concat()
+}
+
+fn packets(stream: &mut Stream) -> Vec<(u8, Vec<u8>)> {
+    let mut packets = vec![];
+    while let Some(packet) = stream.next_frame().unwrap() {
+        packets.push(packet);
+    }
+    packets
+}
+
+fn expected() -> Vec<(u8, Vec<u8>)> {
+    vec![(1, vec![1, 2, 3, 4]), (2, vec![5, 6])]
+}
+
+#[test]
+fn packets_split_across_segments() {
+    let bytes = two_packets();
+    let mut stream = Stream::default();
+    stream.push(1000, TcpFlags::SYN, vec![]);
+    stream.push(1001, TcpFlags::ACK, bytes[..2].to_vec());
+    stream.push(1003, TcpFlags::ACK, bytes[2..9].to_vec());
+    assert_eq!(packets(&mut stream), [(1, vec![1, 2, 3, 4])]);
+    stream.push(1010, TcpFlags::ACK, bytes[9..].to_vec());
+    assert_eq!(packets(&mut stream), [(2, vec![5, 6])]);
+    assert_eq!(stream.leftover(), 0);
+}
+
+#[test]
+fn reordered_segments() {
+    let bytes = two_packets();
+    let mut stream = Stream::default();
+    stream.push(1000, TcpFlags::SYN, vec![]);
+    stream.push(1005, TcpFlags::ACK, bytes[4..8].to_vec());
+    stream.push(1009, TcpFlags::ACK, bytes[8..].to_vec());
+    assert!(packets(&mut stream).is_empty());
+    assert_eq!(stream.leftover(), 8);
+    stream.push(1001, TcpFlags::ACK, bytes[..4].to_vec());
+    assert_eq!(packets(&mut stream), expected());
+    assert_eq!(stream.leftover(), 0);
+}
+
+#[test]
+fn retransmitted_segments() {
+    let bytes = two_packets();
+    let mut stream = Stream::default();
+    stream.push(1000, TcpFlags::SYN, vec![]);
+    stream.push(1001, TcpFlags::ACK, bytes[..7].to_vec());
+    stream.push(1001, TcpFlags::ACK, bytes[..7].to_vec());
+    stream.push(1008, TcpFlags::ACK, bytes[7..].to_vec());
+    stream.push(1008, TcpFlags::ACK, bytes[7..].to_vec());
+    stream.push(1001, TcpFlags::ACK, bytes.clone());
+    assert_eq!(packets(&mut stream), expected());
+    assert_eq!(stream.leftover(), 0);
+}
+
+#[test]
+fn overlapping_segments() {
+    let bytes = two_packets();
+    let mut stream = Stream::default();
+    stream.push(1000, TcpFlags::SYN, vec![]);
+    // A retransmission that resends the end of the first segment along with new bytes
+    stream.push(1001, TcpFlags::ACK, bytes[..6].to_vec());
+    stream.push(1005, TcpFlags::ACK, bytes[4..10].to_vec());
+    // Early segments that overlap each other, and a shorter one at the same offset
+    stream.push(1010, TcpFlags::ACK, bytes[9..11].to_vec());
+    stream.push(1010, TcpFlags::ACK, bytes[9..10].to_vec());
+    stream.push(1011, TcpFlags::ACK, bytes[10..].to_vec());
+    assert_eq!(packets(&mut stream), expected());
+    assert_eq!(stream.leftover(), 0);
+}
+
+#[test]
+fn sequence_numbers_wrap_around() {
+    let bytes = two_packets();
+    let mut stream = Stream::default();
+    stream.push(u32::MAX - 3, TcpFlags::SYN, vec![]);
+    stream.push(u32::MAX - 2, TcpFlags::ACK, bytes[..5].to_vec());
+    stream.push(2, TcpFlags::ACK, bytes[5..].to_vec());
+    assert_eq!(packets(&mut stream), expected());
+}
+
+#[test]
+fn stream_without_handshake() {
+    let bytes = two_packets();
+    let mut stream = Stream::default();
+    stream.push(5000, TcpFlags::ACK, bytes.clone());
+    // Bytes from before the capture started
+    stream.push(4990, TcpFlags::ACK, vec![0; 10]);
+    assert_eq!(packets(&mut stream), expected());
+    assert_eq!(stream.leftover(), 0);
+}
+
+#[test]
+fn gap_that_is_never_filled() {
+    let bytes = two_packets();
+    let mut stream = Stream::default();
+    stream.push(1000, TcpFlags::SYN, vec![]);
+    stream.push(1001, TcpFlags::ACK, bytes[..9].to_vec());
+    stream.push(1011, TcpFlags::ACK, bytes[10..].to_vec());
+    assert_eq!(packets(&mut stream), [(1, vec![1, 2, 3, 4])]);
+    // Two bytes of the second packet and the segment after the gap
+    assert_eq!(stream.leftover(), 4);
+}
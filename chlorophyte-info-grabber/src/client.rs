@@ -0,0 +1,312 @@
+use chlorophyte_terraria_protocol::error::ProtocolError;
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::net_module::{NetModule, SERVER_AUTHOR};
+use chlorophyte_terraria_protocol::packet::{
+    C2SClientUuid, C2SConnect, C2SPlayerAppearance, C2SRequestInitialTileData, C2SRequestWorldInfo,
+    C2SSendPassword, C2SSetBuffs, C2SSetHealth, C2SSetMana, C2SSpawnPlayer, Difficulty, S2CPacket,
+    S2CSendSection, S2CWorldInfo, TerrariaPacket, WriteTerrariaPacket,
+};
+use chlorophyte_terraria_protocol::version::ClientVersion;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{ErrorKind, Read};
+use std::net::{SocketAddr, SocketAddrV4, TcpStream};
+use std::time::{Duration, Instant};
+
+/// The most tile sections kept while joining, vanilla sends about 15 around the spawn
+pub const MAX_SECTIONS: usize = 32;
+
+/// Why we couldn't get the information we wanted from a server
+#[derive(Debug)]
+pub enum GrabError {
+    Io(io::Error),
+    /// The server sent something we couldn't decode
+    Protocol(ProtocolError),
+    /// The server kicked us
+    Booted(String),
+    /// The server wants a password and we don't have one for it
+    PasswordRequired,
+    /// The server asked for the password again after we sent the configured one
+    PasswordRejected,
+    /// The server closed the connection without kicking us
+    Closed,
+}
+
+impl From<io::Error> for GrabError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<ProtocolError> for GrabError {
+    fn from(value: ProtocolError) -> Self {
+        Self::Protocol(value)
+    }
+}
+
+impl Display for GrabError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Protocol(e) => write!(f, "{e}"),
+            Self::Booted(reason) => write!(f, "Booted: {reason}"),
+            Self::PasswordRequired => write!(f, "Password required"),
+            Self::PasswordRejected => write!(f, "Password rejected"),
+            Self::Closed => write!(f, "Connection closed"),
+        }
+    }
+}
+
+/// A connection to a Terraria server that acts like a vanilla client
+pub struct Client {
+    stream: TcpStream,
+    decoder: TerrariaFrameDecoder,
+    /// When we give up on the server
+    deadline: Instant,
+}
+
+impl Client {
+    /// Connects to the server. Everything after that has to be done within `timeout`
+    pub fn connect(addr: SocketAddrV4, timeout: Duration) -> io::Result<Self> {
+        let stream = TcpStream::connect_timeout(&SocketAddr::V4(addr), timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        Ok(Self {
+            stream,
+            decoder: TerrariaFrameDecoder::new(),
+            deadline: Instant::now() + timeout,
+        })
+    }
+
+    pub fn send<P: TerrariaPacket>(&mut self, packet: P) -> Result<(), ProtocolError> {
+        self.stream.write_terraria_packet(packet)
+    }
+
+    /// Waits for the next packet the server sends
+    pub fn next_packet(&mut self) -> Result<S2CPacket, GrabError> {
+        let mut buf = [0u8; 4096];
+        loop {
+            if let Some(packet) = self.decoder.next_packet()? {
+                return Ok(packet);
+            }
+            if Instant::now() > self.deadline {
+                return Err(io::Error::from(ErrorKind::TimedOut).into());
+            }
+            let n = self.stream.read(&mut buf)?;
+            if n == 0 {
+                return Err(GrabError::Closed);
+            }
+            self.decoder.feed(&buf[..n]);
+        }
+    }
+
+    /// Sends the connection request and waits until the server assigns us a player slot.
+    /// If the server asks for a password, `password` is sent once. Nothing else is ever tried,
+    /// and being kicked or asked again after sending it means it was wrong
+    pub fn login(&mut self, password: Option<&str>) -> Result<u8, GrabError> {
+        self.send(C2SConnect {
+            version: ClientVersion::default(),
+        })?;
+        let mut password = password;
+        let mut sent_password = false;
+        loop {
+            match self.next_packet()? {
+                S2CPacket::ConnectionApproved(packet) => return Ok(packet.slot),
+                S2CPacket::PasswordRequired(_) => match password.take() {
+                    Some(password) => {
+                        self.send(C2SSendPassword {
+                            password: password.to_string(),
+                        })?;
+                        sent_password = true;
+                    }
+                    None if sent_password => return Err(GrabError::PasswordRejected),
+                    None => return Err(GrabError::PasswordRequired),
+                },
+                // Vanilla kicks with "Incorrect password.", TShock with "Invalid server password."
+                S2CPacket::FatalError(packet)
+                    if sent_password
+                        && (packet.error.key() == Some("LegacyMultiplayer.1")
+                            || packet.error.render().contains("password")) =>
+                {
+                    return Err(GrabError::PasswordRejected)
+                }
+                S2CPacket::FatalError(packet) => {
+                    return Err(GrabError::Booted(packet.error.render()))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Sends the player data a vanilla client sends after getting a slot
+    /// and requests the world info
+    pub fn request_world_info(&mut self, slot: u8) -> Result<S2CWorldInfo, GrabError> {
+        self.send(player(slot))?;
+        self.send(C2SClientUuid {
+            uuid: random_uuid(),
+        })?;
+        self.send(C2SSetHealth {
+            slot,
+            stat_life: 100,
+            stat_life_max: 100,
+        })?;
+        self.send(C2SSetMana {
+            slot,
+            stat_mana: 20,
+            stat_mana_max: 20,
+        })?;
+        self.send(C2SSetBuffs {
+            slot,
+            buffs: vec![],
+        })?;
+        self.send(C2SRequestWorldInfo)?;
+        loop {
+            match self.next_packet()? {
+                S2CPacket::WorldInfo(world_info) => return Ok(world_info),
+                S2CPacket::FatalError(packet) => {
+                    return Err(GrabError::Booted(packet.error.render()))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Requests the tile data around the spawn, spawns and collects the players and tile
+    /// sections the server sends us until it says we're fully connected
+    pub fn finish_joining(&mut self, slot: u8) -> Result<Joined, GrabError> {
+        self.send(C2SRequestInitialTileData { x: -1, y: -1 })?;
+        let mut players = BTreeMap::new();
+        let mut sections = vec![];
+        let mut chat = vec![];
+        loop {
+            match self.next_packet()? {
+                S2CPacket::CompleteConnectionAndSpawn(_) => self.send(C2SSpawnPlayer {
+                    slot,
+                    spawn_x: -1,
+                    spawn_y: -1,
+                    respawn_timer: 0,
+                    deaths_pve: 0,
+                    deaths_pvp: 0,
+                    // Spawning for the first time
+                    context: 2,
+                })?,
+                S2CPacket::PlayerInfo(info) if info.slot != slot => {
+                    players.insert(
+                        info.slot,
+                        OnlinePlayer {
+                            slot: info.slot,
+                            name: info.name,
+                            difficulty: info.difficulty,
+                        },
+                    );
+                }
+                S2CPacket::PlayerActive(active) if !active.active => {
+                    players.remove(&active.slot);
+                }
+                S2CPacket::SendSection(section) if sections.len() < MAX_SECTIONS => {
+                    sections.push(section);
+                }
+                S2CPacket::NetModule(packet) => {
+                    if let Ok(NetModule::Text { author, text, .. }) = packet.decode() {
+                        chat.push(ChatMessage {
+                            author: (author != SERVER_AUTHOR).then_some(author),
+                            text: text.render(),
+                        });
+                    }
+                }
+                S2CPacket::FinishedConnectingToServer(_) => {
+                    return Ok(Joined {
+                        players: players.into_values().collect(),
+                        sections,
+                        chat,
+                    })
+                }
+                S2CPacket::FatalError(packet) => {
+                    return Err(GrabError::Booted(packet.error.render()))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// What the server sent us after the world info
+pub struct Joined {
+    pub players: Vec<OnlinePlayer>,
+    /// The tile sections around the spawn, still compressed
+    pub sections: Vec<S2CSendSection>,
+    /// The chat messages we got while joining, like the MOTD
+    pub chat: Vec<ChatMessage>,
+}
+
+pub struct ChatMessage {
+    /// The player slot of the author, `None` if the server sent it
+    pub author: Option<u8>,
+    pub text: String,
+}
+
+/// A player that was online while we joined
+pub struct OnlinePlayer {
+    pub slot: u8,
+    pub name: String,
+    pub difficulty: Difficulty,
+}
+
+/// Walks the vanilla join sequence until the server sends the world info, then keeps going
+/// to see who's online and what the spawn looks like. If that part fails, it's an error.
+/// `password` is the one configured for this server, if any
+pub fn grab(
+    addr: SocketAddrV4,
+    timeout: Duration,
+    password: Option<&str>,
+) -> Result<(S2CWorldInfo, Result<Joined, GrabError>), GrabError> {
+    let mut client = Client::connect(addr, timeout)?;
+    let slot = client.login(password)?;
+    let world_info = client.request_world_info(slot)?;
+    Ok((world_info, client.finish_joining(slot)))
+}
+
+/// A fresh, boring player
+fn player(slot: u8) -> C2SPlayerAppearance {
+    C2SPlayerAppearance {
+        slot,
+        skin_variant: 0,
+        hair: 0,
+        name: "Chlorophyte".to_string(),
+        hair_dye: 0,
+        hide_visible_accessory: [false; 10],
+        hide_misc: false,
+        hair_color: (215, 90, 55),
+        skin_color: (255, 125, 90),
+        eye_color: (105, 90, 75),
+        shirt_color: (175, 165, 140),
+        undershirt_color: (160, 180, 215),
+        pants_color: (255, 230, 175),
+        shoe_color: (160, 105, 60),
+        difficulty: Difficulty::Normal,
+        extra_accessory: false,
+        using_biome_torches: false,
+        happy_fun_torch_time: false,
+        unlocked_biome_torches: false,
+        unlocked_super_cart: false,
+        enabled_super_cart: false,
+        used_aegis_crystal: false,
+        used_aegis_fruit: false,
+        used_arcane_crystal: false,
+        used_galaxy_pearl: false,
+        used_gummy_worm: false,
+        used_ambrosia: false,
+        ate_artisan_bread: false,
+    }
+}
+
+/// A random UUID in the format the vanilla client sends
+fn random_uuid() -> String {
+    let hex = |n: usize| {
+        (0..n)
+            .map(|_| char::from_digit(fastrand::u32(..16), 16).unwrap())
+            .collect::<String>()
+    };
+    format!("{}-{}-{}-{}-{}", hex(8), hex(4), hex(4), hex(4), hex(12))
+}
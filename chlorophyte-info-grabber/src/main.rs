@@ -0,0 +1,192 @@
+#![warn(clippy::all, clippy::nursery, clippy::pedantic)]
+
+mod client;
+mod map;
+mod model;
+
+use chlorophyte_terraria_protocol::packet::S2CWorldInfo;
+use chrono::Local;
+use client::{GrabError, Joined};
+use log::{info, warn, Level};
+use model::{ChatRecord, GrabResult, PlayerRecord, ServerRecord, WorldRecord};
+use std::collections::HashMap;
+use std::env::{args, var};
+use std::fs::{create_dir_all, read_to_string, File};
+use std::io::Write;
+use std::net::SocketAddrV4;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread::spawn;
+use std::time::Duration;
+
+/// How long we give a server to send the world info and the online players
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+fn main() {
+    if var("RUST_LOG").is_err() {
+        simple_logger::init_with_level(Level::Info).unwrap();
+    } else {
+        simple_logger::init_with_env().unwrap();
+    }
+    eprintln!("Chlorophyte InfoGrabber - https://github.com/Paddyk45/chlorophyte");
+    let mut positional = vec![];
+    let mut maps_dir = None;
+    let mut passwords = HashMap::new();
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--maps" => {
+                let dir = PathBuf::from(args.next().expect("No directory specified for --maps"));
+                create_dir_all(&dir).expect("Failed to create maps directory");
+                maps_dir = Some(dir);
+            }
+            "--passwords" => {
+                let file = args.next().expect("No file specified for --passwords");
+                passwords = read_passwords(&file);
+            }
+            flag if flag.starts_with("--") => panic!("Unknown flag: {flag}"),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    let Some(results_file) = positional.next() else {
+        panic!("No mass finder results file specified");
+    };
+    let threads = positional.next().map_or(16, |t| {
+        t.parse::<usize>()
+            .expect("Failed to parse threads as usize")
+    });
+
+    // The first word of every line of the mass finder results is the address
+    let addresses = read_to_string(results_file)
+        .expect("Failed to read results file")
+        .lines()
+        .filter_map(|l| l.split_whitespace().next()?.parse::<SocketAddrV4>().ok())
+        .collect::<Vec<SocketAddrV4>>();
+    info!(
+        "Grabbing info from {} servers with {threads} threads",
+        addresses.len()
+    );
+
+    if !passwords.is_empty() {
+        info!("Loaded passwords for {} servers", passwords.len());
+    }
+
+    let queue = Arc::new(Mutex::new(addresses));
+    let passwords = Arc::new(passwords);
+    let (tx, rx) = channel();
+    for _ in 0..threads {
+        let queue = queue.clone();
+        let tx = tx.clone();
+        let maps_dir = maps_dir.clone();
+        let passwords = passwords.clone();
+        spawn(move || loop {
+            let Some(addr) = queue.lock().unwrap().pop() else {
+                break;
+            };
+            let password = passwords.get(&addr).map(String::as_str);
+            tx.send(grab(addr, maps_dir.as_deref(), password)).unwrap();
+        });
+    }
+    drop(tx);
+
+    let file_name = format!(
+        "chlorophyte_info_grabber_results-{}.jsonl",
+        Local::now().format("%y-%m-%d_%H_%M_%S")
+    );
+    // The most active servers first
+    let mut records = rx.into_iter().collect::<Vec<ServerRecord>>();
+    records.sort_by_key(|r| std::cmp::Reverse(r.player_count()));
+    let mut f = File::create(&file_name).expect("Failed to open files");
+    for record in records {
+        let line = serde_json::to_string(&record).expect("Failed to serialize record");
+        f.write_all(format!("{line}\n").as_bytes())
+            .expect("Failed to write line to file");
+    }
+
+    println!("Results written to {file_name}");
+}
+
+fn grab(address: SocketAddrV4, maps_dir: Option<&Path>, password: Option<&str>) -> ServerRecord {
+    let result = match client::grab(address, TIMEOUT, password) {
+        Ok((world_info, joined)) => {
+            let world = Box::new(WorldRecord::from(&world_info));
+            let joined = joined
+                .map_err(|e| info!("{address}: Failed to finish joining: {e}"))
+                .ok();
+            let map = maps_dir
+                .zip(joined.as_ref())
+                .and_then(|(dir, joined)| save_map(address, dir, &world_info, joined));
+            let (players, chat) = joined
+                .map(|j| {
+                    (
+                        j.players
+                            .into_iter()
+                            .map(PlayerRecord::from)
+                            .collect::<Vec<_>>(),
+                        j.chat.into_iter().map(ChatRecord::from).collect(),
+                    )
+                })
+                .unzip();
+            info!(
+                "{address}: {} ({} players)",
+                world.name,
+                players.as_ref().map_or(0, Vec::len)
+            );
+            GrabResult::Ok {
+                world,
+                player_count: players.as_ref().map(Vec::len),
+                players,
+                chat,
+                map,
+            }
+        }
+        Err(GrabError::PasswordRequired) => GrabResult::PasswordRequired,
+        Err(GrabError::PasswordRejected) => GrabResult::PasswordRejected,
+        Err(GrabError::Booted(reason)) => GrabResult::Booted { reason },
+        Err(e) => GrabResult::Failed {
+            error: e.to_string(),
+        },
+    };
+    ServerRecord { address, result }
+}
+
+/// Reads the passwords of the servers we're allowed to log into, one `<ip>:<port> <password>`
+/// per line. Empty lines and lines starting with `#` are skipped
+fn read_passwords(file: &str) -> HashMap<SocketAddrV4, String> {
+    let mut passwords = HashMap::new();
+    let content = read_to_string(file).expect("Failed to read passwords file");
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // The password is everything after the first space, so it can contain spaces itself
+        let Some((address, password)) = line.split_once(' ') else {
+            panic!("Line {} of the passwords file has no password", i + 1);
+        };
+        let address = address
+            .parse::<SocketAddrV4>()
+            .unwrap_or_else(|_| panic!("Invalid address on line {} of the passwords file", i + 1));
+        passwords.insert(address, password.to_string());
+    }
+    passwords
+}
+
+/// Renders the tile sections the server sent into `<dir>/<ip>_<port>.png`
+fn save_map(
+    address: SocketAddrV4,
+    dir: &Path,
+    world_info: &S2CWorldInfo,
+    joined: &Joined,
+) -> Option<PathBuf> {
+    let grid = map::stitch(&joined.sections)?;
+    let path = dir.join(format!("{}_{}.png", address.ip(), address.port()));
+    match map::save_png(&path, &grid, world_info) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            warn!("{address}: Failed to save map: {e}");
+            None
+        }
+    }
+}
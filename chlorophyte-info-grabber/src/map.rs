@@ -0,0 +1,260 @@
+use crate::client::MAX_SECTIONS;
+use chlorophyte_terraria_protocol::packet::{S2CSendSection, S2CWorldInfo};
+use chlorophyte_terraria_protocol::tile::{Bounds, LiquidType, Tile, TileGrid, MAX_SECTION_TILES};
+use chlorophyte_terraria_protocol::types::Rgb;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Map colours of common tiles, from the game's `MapHelper`.
+/// Tiles with several colours (like gems or paintings) only get their first one
+const TILE_COLORS: &[(u16, Rgb)] = &[
+    (0, (151, 107, 75)),
+    (1, (128, 128, 128)),
+    (2, (28, 216, 94)),
+    (3, (27, 197, 109)),
+    (4, (253, 221, 3)),
+    (5, (151, 107, 75)),
+    (6, (140, 101, 80)),
+    (7, (150, 67, 22)),
+    (8, (185, 164, 23)),
+    (9, (185, 194, 195)),
+    (10, (119, 105, 79)),
+    (11, (119, 105, 79)),
+    (12, (174, 24, 69)),
+    (19, (191, 142, 111)),
+    (21, (174, 129, 92)),
+    (22, (98, 95, 167)),
+    (23, (141, 137, 223)),
+    (24, (122, 116, 218)),
+    (25, (109, 90, 128)),
+    (26, (119, 101, 125)),
+    (27, (226, 196, 49)),
+    (28, (151, 79, 80)),
+    (30, (170, 120, 84)),
+    (37, (104, 86, 84)),
+    (38, (144, 148, 144)),
+    (39, (181, 62, 59)),
+    (40, (146, 81, 68)),
+    (41, (66, 84, 109)),
+    (43, (84, 100, 63)),
+    (44, (107, 68, 99)),
+    (45, (185, 164, 23)),
+    (46, (185, 194, 195)),
+    (47, (150, 67, 22)),
+    (48, (128, 128, 128)),
+    (52, (23, 177, 76)),
+    (53, (186, 168, 84)),
+    (54, (200, 246, 254)),
+    (56, (43, 40, 84)),
+    (57, (68, 68, 76)),
+    (58, (142, 66, 66)),
+    (59, (92, 68, 73)),
+    (60, (143, 215, 29)),
+    (61, (135, 196, 26)),
+    (62, (121, 176, 24)),
+    (69, (190, 150, 92)),
+    (70, (93, 127, 255)),
+    (71, (182, 175, 130)),
+    (72, (182, 175, 130)),
+    (73, (27, 197, 109)),
+    (74, (96, 197, 27)),
+    (75, (36, 36, 36)),
+    (76, (142, 66, 66)),
+    (80, (73, 120, 17)),
+    (107, (11, 80, 143)),
+    (108, (91, 169, 169)),
+    (109, (78, 193, 227)),
+    (110, (48, 186, 135)),
+    (111, (128, 26, 52)),
+    (112, (103, 98, 122)),
+    (115, (48, 208, 234)),
+    (116, (238, 225, 218)),
+    (117, (181, 172, 190)),
+    (118, (238, 225, 218)),
+    (120, (92, 68, 73)),
+    (123, (106, 107, 118)),
+    (124, (73, 51, 36)),
+    (147, (211, 236, 241)),
+    (148, (181, 211, 210)),
+    (151, (190, 171, 94)),
+    (161, (144, 195, 232)),
+    (162, (184, 219, 240)),
+    (163, (174, 145, 214)),
+    (164, (218, 182, 204)),
+    (166, (129, 125, 93)),
+    (167, (62, 82, 114)),
+    (168, (132, 157, 127)),
+    (169, (152, 171, 198)),
+    (189, (223, 255, 255)),
+    (191, (151, 107, 75)),
+    (192, (26, 196, 84)),
+    (199, (208, 80, 80)),
+    (200, (216, 152, 144)),
+    (203, (128, 44, 45)),
+    (204, (125, 55, 65)),
+    (211, (40, 255, 100)),
+    (221, (239, 90, 50)),
+    (222, (231, 96, 228)),
+    (223, (57, 85, 101)),
+    (225, (224, 194, 101)),
+    (226, (141, 56, 0)),
+    (229, (254, 121, 2)),
+    (230, (199, 77, 12)),
+    (234, (53, 44, 41)),
+    (367, (168, 178, 204)),
+    (368, (50, 46, 104)),
+    (396, (198, 124, 78)),
+    (397, (212, 192, 100)),
+    (398, (100, 82, 126)),
+    (399, (77, 76, 66)),
+    (400, (96, 68, 117)),
+    (401, (68, 60, 51)),
+    (402, (174, 168, 186)),
+    (403, (205, 152, 186)),
+    (404, (212, 148, 88)),
+    (408, (85, 83, 82)),
+    (477, (28, 216, 94)),
+    (492, (78, 193, 227)),
+];
+
+/// Map colours of common walls, from the game's `MapHelper`
+const WALL_COLORS: &[(u16, Rgb)] = &[
+    (1, (52, 52, 52)),
+    (2, (88, 61, 46)),
+    (3, (61, 58, 78)),
+    (4, (73, 51, 36)),
+    (5, (52, 52, 52)),
+    (7, (27, 31, 42)),
+    (8, (31, 39, 26)),
+    (9, (41, 28, 36)),
+    (16, (88, 61, 46)),
+    (28, (38, 38, 38)),
+    (40, (86, 110, 112)),
+    (54, (103, 70, 46)),
+    (59, (88, 61, 46)),
+    (63, (30, 80, 48)),
+    (64, (53, 80, 30)),
+    (65, (30, 80, 48)),
+    (66, (30, 80, 48)),
+    (67, (53, 80, 30)),
+    (69, (64, 62, 80)),
+    (81, (89, 46, 39)),
+    (83, (80, 34, 34)),
+    (86, (138, 73, 38)),
+    (87, (73, 22, 0)),
+    (178, (112, 115, 140)),
+    (180, (35, 31, 70)),
+    (187, (103, 70, 46)),
+    (216, (113, 92, 53)),
+];
+
+const DEFAULT_TILE: Rgb = (128, 128, 128);
+const DEFAULT_WALL: Rgb = (60, 50, 45);
+const SKY_TOP: Rgb = (50, 40, 255);
+const SKY_BOTTOM: Rgb = (145, 185, 255);
+const DIRT_LAYER: Rgb = (88, 61, 46);
+const ROCK_LAYER: Rgb = (74, 67, 60);
+const UNDERWORLD: Rgb = (50, 44, 38);
+
+fn lookup(table: &[(u16, Rgb)], id: u16) -> Option<Rgb> {
+    table.iter().find(|(i, _)| *i == id).map(|(_, c)| *c)
+}
+
+/// The colour of the tile on the map: blocks first, then liquids, then walls
+fn tile_color(tile: &Tile, y: i32, world_info: &S2CWorldInfo) -> Rgb {
+    if let Some(tile_type) = tile.tile_type.filter(|_| !tile.invisible_block) {
+        return lookup(TILE_COLORS, tile_type).unwrap_or(DEFAULT_TILE);
+    }
+    if tile.liquid > 0 {
+        return match tile.liquid_type {
+            LiquidType::Water => (9, 61, 191),
+            LiquidType::Lava => (253, 32, 3),
+            LiquidType::Honey => (254, 194, 20),
+            LiquidType::Shimmer => (189, 150, 255),
+        };
+    }
+    if tile.wall != 0 && !tile.invisible_wall {
+        return lookup(WALL_COLORS, tile.wall).unwrap_or(DEFAULT_WALL);
+    }
+    background(y, world_info)
+}
+
+/// The colour of empty tiles, which depends on the layer they're in
+fn background(y: i32, world_info: &S2CWorldInfo) -> Rgb {
+    let surface = i32::from(world_info.world_surface);
+    if y < surface {
+        // The sky fades from dark blue to light blue towards the surface
+        let t = f64::from(y.max(0)) / f64::from(surface.max(1));
+        let mix = |a: u8, b: u8| {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let c = (f64::from(b) - f64::from(a)).mul_add(t, f64::from(a)) as u8;
+            c
+        };
+        (
+            mix(SKY_TOP.0, SKY_BOTTOM.0),
+            mix(SKY_TOP.1, SKY_BOTTOM.1),
+            mix(SKY_TOP.2, SKY_BOTTOM.2),
+        )
+    } else if y < i32::from(world_info.rock_layer) {
+        DIRT_LAYER
+    } else if y < i32::from(world_info.max_tiles_y) - 200 {
+        ROCK_LAYER
+    } else {
+        UNDERWORLD
+    }
+}
+
+/// The most tiles the stitched sections may cover together
+const MAX_MAP_TILES: usize = MAX_SECTIONS * MAX_SECTION_TILES;
+
+/// Decodes the sections one at a time and pastes them into one grid.
+/// Sections that can't be decoded are skipped, as are sections once [`MAX_MAP_TILES`] tiles
+/// have been decoded
+pub fn stitch(sections: &[S2CSendSection]) -> Option<TileGrid> {
+    // Only the headers are decoded first, to know how large the grid has to be
+    let mut tiles_left = MAX_MAP_TILES;
+    let mut kept = vec![];
+    for section in sections {
+        let Ok(bounds) = section.bounds() else {
+            continue;
+        };
+        let Some(left) = tiles_left.checked_sub(bounds.width * bounds.height) else {
+            break;
+        };
+        tiles_left = left;
+        kept.push((section, bounds));
+    }
+    let bounds = kept.iter().map(|(_, b)| *b).collect::<Vec<Bounds>>();
+    let mut grid = TileGrid::covering(&bounds, MAX_MAP_TILES)?;
+    for (section, _) in kept {
+        if let Ok(tiles) = section.tiles() {
+            grid.paste(&tiles);
+        }
+    }
+    Some(grid)
+}
+
+/// Renders the grid as a PNG with one pixel per tile
+pub fn save_png(path: &Path, grid: &TileGrid, world_info: &S2CWorldInfo) -> io::Result<()> {
+    let mut pixels = Vec::with_capacity(grid.width * grid.height * 3);
+    for (y, row) in (grid.y..).zip(grid.rows()) {
+        for tile in row {
+            pixels.extend_from_slice(&<[u8; 3]>::from(tile_color(tile, y, world_info)));
+        }
+    }
+
+    let too_large = |_| io::Error::new(io::ErrorKind::InvalidInput, "Map is too large");
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        u32::try_from(grid.width).map_err(too_large)?,
+        u32::try_from(grid.height).map_err(too_large)?,
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut w| w.write_image_data(&pixels))
+        .map_err(io::Error::other)
+}
@@ -0,0 +1,219 @@
+use crate::client::{ChatMessage, OnlinePlayer};
+use chlorophyte_terraria_protocol::packet::{Difficulty, S2CWorldInfo};
+use serde::Serialize;
+use std::fmt::Write;
+use std::net::SocketAddrV4;
+use std::path::PathBuf;
+
+/// One line of the results file
+#[derive(Serialize)]
+pub struct ServerRecord {
+    pub address: SocketAddrV4,
+    #[serde(flatten)]
+    pub result: GrabResult,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum GrabResult {
+    Ok {
+        world: Box<WorldRecord>,
+        /// `None` if the server didn't let us finish joining
+        player_count: Option<usize>,
+        players: Option<Vec<PlayerRecord>>,
+        /// The chat messages sent while joining, like the MOTD
+        chat: Option<Vec<ChatRecord>>,
+        /// Where the map of the spawn area was saved, if `--maps` was given
+        map: Option<PathBuf>,
+    },
+    PasswordRequired,
+    /// The password from `--passwords` was wrong
+    PasswordRejected,
+    Booted {
+        reason: String,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl ServerRecord {
+    /// How many players were online, 0 if we don't know
+    pub const fn player_count(&self) -> usize {
+        match self.result {
+            GrabResult::Ok {
+                player_count: Some(count),
+                ..
+            } => count,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PlayerRecord {
+    pub slot: u8,
+    pub name: String,
+    pub difficulty: &'static str,
+}
+
+impl From<OnlinePlayer> for PlayerRecord {
+    fn from(p: OnlinePlayer) -> Self {
+        Self {
+            slot: p.slot,
+            name: p.name,
+            difficulty: match p.difficulty {
+                Difficulty::Normal => "classic",
+                Difficulty::Mediumcore => "mediumcore",
+                Difficulty::Hardcore => "hardcore",
+                Difficulty::Journey => "journey",
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ChatRecord {
+    /// The player slot of the author, `None` if the server sent it
+    pub author: Option<u8>,
+    pub text: String,
+}
+
+impl From<ChatMessage> for ChatRecord {
+    fn from(m: ChatMessage) -> Self {
+        Self {
+            author: m.author,
+            text: m.text,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct WorldRecord {
+    pub name: String,
+    pub id: i32,
+    /// The world's GUID, as hex
+    pub unique_id: String,
+    pub width: i16,
+    pub height: i16,
+    pub size: &'static str,
+    pub game_mode: &'static str,
+    pub hardmode: bool,
+    pub time: i32,
+    pub day_time: bool,
+    /// The in-game clock, like `4:30 AM`
+    pub clock: String,
+    pub moon_phase: u8,
+    pub blood_moon: bool,
+    pub eclipse: bool,
+    pub spawn: (i16, i16),
+    /// The invasion currently happening
+    pub invasion: Option<&'static str>,
+    /// The secret seeds the world was generated with. The seed text itself is never sent,
+    /// but the special seeds can be told apart by their flags
+    pub special_seeds: Vec<&'static str>,
+    pub downed_bosses: Vec<&'static str>,
+}
+
+impl From<&S2CWorldInfo> for WorldRecord {
+    fn from(w: &S2CWorldInfo) -> Self {
+        let f = &w.flags;
+        let names = |table: &[(&'static str, bool)]| {
+            table
+                .iter()
+                .filter(|(_, set)| *set)
+                .map(|(name, _)| *name)
+                .collect()
+        };
+        Self {
+            name: w.world_name.clone(),
+            id: w.world_id,
+            unique_id: w.unique_id.iter().fold(String::new(), |mut s, b| {
+                let _ = write!(s, "{b:02x}");
+                s
+            }),
+            width: w.max_tiles_x,
+            height: w.max_tiles_y,
+            size: match w.max_tiles_x {
+                4200 => "small",
+                6400 => "medium",
+                8400 => "large",
+                _ => "custom",
+            },
+            game_mode: match w.game_mode {
+                0 => "classic",
+                1 => "expert",
+                2 => "master",
+                3 => "journey",
+                _ => "unknown",
+            },
+            hardmode: f.hardmode,
+            time: w.time,
+            day_time: w.day_time,
+            clock: clock(w.time, w.day_time),
+            moon_phase: w.moon_phase,
+            blood_moon: w.blood_moon,
+            eclipse: w.eclipse,
+            spawn: (w.spawn_x, w.spawn_y),
+            invasion: match w.invasion_type {
+                0 => None,
+                1 => Some("goblin army"),
+                2 => Some("frost legion"),
+                3 => Some("pirate invasion"),
+                4 => Some("martian madness"),
+                _ => Some("unknown"),
+            },
+            special_seeds: names(&[
+                ("drunk world", f.drunk_world),
+                ("for the worthy", f.for_the_worthy),
+                ("celebrationmk10", f.tenth_anniversary_world),
+                ("the constant", f.dont_starve_world),
+                ("not the bees", f.not_the_bees_world),
+                ("don't dig up", f.remix_world),
+                ("no traps", f.no_traps_world),
+                ("get fixed boi", f.zenith_world),
+            ]),
+            downed_bosses: names(&[
+                ("King Slime", f.downed_king_slime),
+                ("Eye of Cthulhu", f.downed_eye_of_cthulhu),
+                ("Eater of Worlds / Brain of Cthulhu", f.downed_evil_boss),
+                ("Queen Bee", f.downed_queen_bee),
+                ("Skeletron", f.downed_skeletron),
+                ("Deerclops", f.downed_deerclops),
+                ("Wall of Flesh", f.hardmode),
+                ("Queen Slime", f.downed_queen_slime),
+                ("The Destroyer", f.downed_destroyer),
+                ("The Twins", f.downed_twins),
+                ("Skeletron Prime", f.downed_skeletron_prime),
+                ("Plantera", f.downed_plantera),
+                ("Golem", f.downed_golem),
+                ("Duke Fishron", f.downed_duke_fishron),
+                ("Empress of Light", f.downed_empress_of_light),
+                ("Lunatic Cultist", f.downed_lunatic_cultist),
+                ("Moon Lord", f.downed_moon_lord),
+            ]),
+        }
+    }
+}
+
+/// Turns the world time into the clock shown by a watch.
+/// Days start at 4:30 AM and last 54000 ticks, nights start at 7:30 PM and last 32400 ticks
+fn clock(time: i32, day_time: bool) -> String {
+    let mut hours = f64::from(time) / 3600.0;
+    if day_time {
+        hours += 4.5;
+    } else {
+        hours += 19.5;
+    }
+    let hours = hours % 24.0;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let (h, m) = (hours as u32, ((hours.fract()) * 60.0) as u32);
+    let (h, suffix) = match h {
+        0 => (12, "AM"),
+        1..=11 => (h, "AM"),
+        12 => (12, "PM"),
+        _ => (h - 12, "PM"),
+    };
+    format!("{h}:{m:02} {suffix}")
+}
@@ -0,0 +1,78 @@
+use chlorophyte_mock_server::{Behavior, MockConfig, MockServer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, read_dir, read_to_string, remove_dir_all, write};
+use std::net::SocketAddr;
+use std::process::Command;
+
+fn start(behavior: Behavior) -> SocketAddr {
+    MockServer::start(MockConfig {
+        behavior,
+        world_name: "Loopback".to_string(),
+        players: vec!["Alice".to_string()],
+        motd: Some("Welcome!".to_string()),
+        ..MockConfig::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn grabs_mock_servers() {
+    let vanilla = start(Behavior::Vanilla);
+    let password = start(Behavior::PasswordProtected("hunter2".to_string()));
+    let wrong_password = start(Behavior::PasswordProtected("hunter2".to_string()));
+    let no_password = start(Behavior::PasswordProtected("hunter2".to_string()));
+    let kick = start(Behavior::Kick("You are banned.".to_string()));
+
+    let dir = std::env::temp_dir().join(format!("chlorophyte-info-grabber-{}", std::process::id()));
+    create_dir_all(&dir).unwrap();
+    let results = [vanilla, password, wrong_password, no_password, kick]
+        .iter()
+        .map(|a| format!("{a} (Loopback)\n"))
+        .collect::<String>();
+    write(dir.join("results.txt"), results).unwrap();
+    write(
+        dir.join("passwords.txt"),
+        format!("# Test servers\n{password} hunter2\n{wrong_password} hunter3\n"),
+    )
+    .unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_chlorophyte-info-grabber"))
+        .current_dir(&dir)
+        .args(["results.txt", "4", "--passwords", "passwords.txt"])
+        .env("RUST_LOG", "warn")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let results_file = read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "jsonl"))
+        .unwrap();
+    let records = read_to_string(results_file)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<Value>(l).unwrap())
+        .map(|r| (r["address"].as_str().unwrap().to_string(), r))
+        .collect::<HashMap<_, _>>();
+    remove_dir_all(&dir).unwrap();
+
+    let vanilla = &records[&vanilla.to_string()];
+    assert_eq!(vanilla["status"], "ok");
+    assert_eq!(vanilla["world"]["name"], "Loopback");
+    assert_eq!(vanilla["players"][0]["name"], "Alice");
+    assert_eq!(vanilla["chat"][0]["text"], "Welcome!");
+    assert_eq!(records[&password.to_string()]["status"], "ok");
+    assert_eq!(
+        records[&wrong_password.to_string()]["status"],
+        "password_rejected"
+    );
+    assert_eq!(
+        records[&no_password.to_string()]["status"],
+        "password_required"
+    );
+    let kick = &records[&kick.to_string()];
+    assert_eq!(kick["status"], "booted");
+    assert_eq!(kick["reason"], "You are banned.");
+}
@@ -0,0 +1,69 @@
+//! Saves how far a scan got, so it can be continued after the scanner was stopped
+
+use crate::model::TerrariaServer;
+use crate::Shard;
+use matscan_ranges::targets::ScanRanges;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs::{read_to_string, rename, write};
+use std::io;
+use std::path::Path;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub seed: u64,
+    /// See [`config_hash`]
+    pub config_hash: u64,
+    /// The position in the shuffled order of the targets to continue at. Targets before it
+    /// were sent a SYN and had the time to answer
+    pub position: u64,
+    pub found_servers: Vec<TerrariaServer>,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_str(&read_to_string(path)?)?)
+    }
+
+    /// Replaces the file at `path` at once, so it's never left half-written.
+    /// Saves to the same path must not run at the same time, as they share the temporary file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let temp = path.with_extension("tmp");
+        write(&temp, serde_json::to_string(self)?)?;
+        rename(temp, path)
+    }
+}
+
+/// Identifies the order the targets are scanned in. Resuming a scan only works with the same
+/// ranges, seed and shard, as the position means nothing otherwise.
+/// FNV-1a, as the hash has to stay the same between builds
+pub fn config_hash(ranges: &ScanRanges, seed: u64, shard: Shard) -> u64 {
+    let mut hash = 0xCBF2_9CE4_8422_2325u64;
+    let mut feed = |bytes: &[u8]| {
+        for b in bytes {
+            hash = (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    };
+    for range in ranges.ranges() {
+        feed(&range.addr_start.octets());
+        feed(&range.addr_end.octets());
+        feed(&range.port_start.to_le_bytes());
+        feed(&range.port_end.to_le_bytes());
+    }
+    feed(&seed.to_le_bytes());
+    feed(&shard.index().to_le_bytes());
+    feed(&shard.count().to_le_bytes());
+    hash
+}
+
+/// The checkpoint is from a scan with other ranges, another seed or another shard
+#[derive(Debug)]
+pub struct CheckpointMismatch;
+
+impl Display for CheckpointMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(
+            "The checkpoint is from a scan with other ranges, another seed or another shard",
+        )
+    }
+}
@@ -0,0 +1,88 @@
+use chlorophyte_mass_finder::Shard;
+use matscan_ranges::targets::ScanRange;
+use std::env::args;
+use std::path::PathBuf;
+
+/// The tModLoader version we claim to be if the server didn't tell us its own
+pub const DEFAULT_TMODLOADER_VERSION: &str = "2023.8.3.4";
+
+pub struct Args {
+    /// The ranges to scan
+    pub ranges: Vec<ScanRange>,
+    /// The maximum amount of SYNs sent per second
+    pub max_pps: u64,
+    /// Whether to reconnect to servers that booted us because of a version mismatch
+    /// with older versions until one is accepted
+    pub probe_versions: bool,
+    /// Whether to reconnect to tModLoader servers as a tModLoader client to get their mod list
+    pub probe_mods: bool,
+    /// Decides the order the targets are scanned in, random if not given
+    pub seed: Option<u64>,
+    /// The part of the targets to scan when the scan is split between machines
+    pub shard: Option<Shard>,
+    /// The checkpoint of a scan of the same ranges to continue
+    pub resume: Option<PathBuf>,
+}
+
+impl Args {
+    /// Parses the command line:
+    /// `<ranges> [pps] [--probe-versions] [--probe-mods] [--seed <seed>] [--shard <n>/<m>]
+    /// [--resume <checkpoint>]`
+    pub fn parse() -> Self {
+        let mut positional = vec![];
+        let mut probe_versions = false;
+        let mut probe_mods = false;
+        let mut seed = None;
+        let mut shard = None;
+        let mut resume = None;
+        let mut args = args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--probe-versions" => probe_versions = true,
+                "--probe-mods" => probe_mods = true,
+                "--seed" => {
+                    let value = args.next().expect("No seed specified for --seed");
+                    seed = Some(value.parse().expect("Failed to parse seed as u64"));
+                }
+                "--shard" => {
+                    let value = args.next().expect("No shard specified for --shard");
+                    let parsed = value.parse::<Shard>();
+                    shard = Some(parsed.expect("Failed to parse shard, it should look like 1/4"));
+                }
+                "--resume" => {
+                    let file = args.next().expect("No checkpoint specified for --resume");
+                    resume = Some(PathBuf::from(file));
+                }
+                flag if flag.starts_with("--") => panic!("Unknown flag: {flag}"),
+                _ => positional.push(arg),
+            }
+        }
+        // Every shard has to shuffle the targets the same way, or they would overlap
+        assert!(
+            shard.is_none() || seed.is_some() || resume.is_some(),
+            "--shard needs the same --seed on every machine"
+        );
+        let mut positional = positional.into_iter();
+
+        let Some(ranges) = positional.next() else {
+            panic!("No range specified");
+        };
+        let ranges = ranges
+            .split(',')
+            .map(|i| i.parse::<ScanRange>().expect("Failed to parse scan range"))
+            .collect::<Vec<ScanRange>>();
+        let max_pps = positional.next().map_or(50_000, |pps| {
+            pps.parse().expect("Failed to parse max pps as u64")
+        });
+
+        Self {
+            ranges,
+            max_pps,
+            probe_versions,
+            probe_mods,
+            seed,
+            shard,
+            resume,
+        }
+    }
+}
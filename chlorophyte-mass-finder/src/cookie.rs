@@ -0,0 +1,30 @@
+//! Masscan-style SYN cookies: the sequence number of a SYN is a keyed hash of where it was sent
+//! to and from, so a SYN+ACK can be checked without remembering every SYN we sent
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::net::SocketAddrV4;
+
+/// A secret key, so nobody can guess our sequence numbers and fake answers
+#[derive(Clone, Debug, Default)]
+pub struct SynCookies {
+    key: RandomState,
+}
+
+impl SynCookies {
+    /// Cookies with a random key
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The sequence number of the SYN sent from `source_port` to `addr`
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn sequence(&self, addr: SocketAddrV4, source_port: u16) -> u32 {
+        self.key.hash_one((addr, source_port)) as u32
+    }
+
+    /// Whether a SYN+ACK from `addr` to `source_port` acknowledges a SYN we sent
+    pub fn check(&self, addr: SocketAddrV4, source_port: u16, acknowledgement: u32) -> bool {
+        acknowledgement == self.sequence(addr, source_port).wrapping_add(1)
+    }
+}
@@ -0,0 +1,11 @@
+//! Finds Terraria servers by sending SYNs to whole ranges from a stateless TCP stack
+//! and asking everything that answers to let us join
+
+pub mod checkpoint;
+pub mod cookie;
+pub mod model;
+pub mod permutation;
+pub mod results;
+pub mod scanner;
+
+pub use scanner::{ScanConfig, ScanHandle, Scanner, Shard};
@@ -1,27 +1,26 @@
 #![warn(clippy::all, clippy::nursery, clippy::pedantic)]
 
-mod model;
-mod scanner;
+mod cli;
 
+use chlorophyte_mass_finder::checkpoint::Checkpoint;
+use chlorophyte_mass_finder::model::TerrariaServer;
+use chlorophyte_mass_finder::{results, ScanConfig, Scanner};
+use chlorophyte_terraria_protocol::version::{ClientVersion, LATEST, VERSIONS};
 use chrono::Local;
-use log::{info, Level};
+use cli::Args;
+use log::{info, warn, Level};
 use matscan_ranges::exclude;
-use matscan_ranges::targets::{ScanRange, ScanRanges};
-use matscan_tcp::{SourcePort, StatelessTcp};
+use matscan_ranges::targets::ScanRanges;
+use std::collections::{HashMap, HashSet};
 use std::env::{args, var};
-use std::fs::File;
+use std::fs::{read_to_string, remove_file, File};
 use std::io::Write;
 use std::net::SocketAddrV4;
+use std::path::PathBuf;
 use std::process::exit;
-use std::thread::{sleep, spawn};
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 fn main() {
-    ctrlc::set_handler(|| {
-        dbg!(scanner::get_found_servers());
-        exit(130);
-    })
-    .unwrap();
     if var("RUST_LOG").is_err() {
         simple_logger::init_with_level(Level::Info).unwrap();
     } else {
@@ -36,62 +35,179 @@ fn main() {
     let splash = splashes[fastrand::usize(..splashes.len())];
     println!("{banner}{splash}\n");
     eprintln!("Chlorophyte MassFinder - https://github.com/Paddyk45/chlorophyte");
+    let raw_args = args().collect::<Vec<String>>();
+    if raw_args.get(1).is_some_and(|a| a == "merge") {
+        merge(&raw_args[2..]);
+        return;
+    }
+    let args = Args::parse();
     let mut ranges = ScanRanges::new();
-
-    let Some(includes) = args().nth(1) else {
-        panic!("No range specified");
-    };
-    let includes = includes
-        .split(',')
-        .map(|i| i.parse::<ScanRange>().expect("Failed to parse scan range"))
-        .collect::<Vec<ScanRange>>();
-    ranges.extend(includes);
+    ranges.extend(args.ranges);
 
     let before_exclude = ranges.count();
     ranges.exclude_ranges(exclude::parse(include_str!("exclude.conf")).unwrap());
     info!("Excluded {} IP-addresses", before_exclude - ranges.count());
 
-    let tcp = StatelessTcp::new(SourcePort::Range {
-        min: 61000,
-        max: 65000,
-    });
     info!(
         "Scanning {} ranges @ {} IP-addresses",
         ranges.ranges().len(),
         ranges.count()
     );
-    let start_time = Instant::now();
-    let mut tcp_w = tcp.write.clone();
-    spawn(|| scanner::receiver(tcp_w, tcp.read));
-    spawn(|| scanner::garbage_collector());
-    tcp_w = tcp.write.clone();
-    scanner::synner(ranges, tcp_w);
-    println!("SYNner done! Sleeping 3 seconds...");
-    sleep(Duration::from_secs(3));
+    let checkpoint = args.resume.as_ref().map(|path| {
+        Checkpoint::load(path)
+            .unwrap_or_else(|e| panic!("Failed to read checkpoint {}: {e}", path.display()))
+    });
+    let mut config = ScanConfig::new(ranges);
+    config.max_pps = args.max_pps;
+    // A resumed scan has to visit the targets in the same order as before
+    if let Some(seed) = args.seed.or_else(|| checkpoint.as_ref().map(|c| c.seed)) {
+        config.seed = seed;
+    }
+    if let Some(shard) = args.shard {
+        info!("Scanning shard {shard}");
+        config.shard = shard;
+    }
+    // Logged so the scan can be repeated in the same order
+    info!("Seed: {}", config.seed);
+    let checkpoint_path = args.resume.clone().unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "chlorophyte_mass_finder_checkpoint-{}.json",
+            Local::now().format("%y-%m-%d_%H_%M_%S")
+        ))
+    });
+    config.checkpoint = Some(checkpoint_path.clone());
+    // The found servers are logged by the scanner, so nobody has to listen
+    let (mut scanner, _) = Scanner::new(config);
+    if let Some(checkpoint) = checkpoint {
+        scanner.resume(checkpoint).unwrap_or_else(|e| panic!("{e}"));
+    }
+    let handle = scanner.handle();
+    let interrupted_checkpoint = checkpoint_path.clone();
+    ctrlc::set_handler(move || {
+        println!("Interrupted!");
+        match handle.save_checkpoint(&interrupted_checkpoint) {
+            Ok(()) => println!(
+                "Checkpoint written to {0}, continue the scan with --resume {0}",
+                interrupted_checkpoint.display()
+            ),
+            Err(e) => warn!("Failed to save checkpoint: {e}"),
+        }
+        write_results(handle.found_servers().iter().map(results::format_line));
+        exit(130);
+    })
+    .unwrap();
 
-    let mut found_servers = scanner::get_found_servers();
+    let start_time = Instant::now();
+    let mut found_servers = scanner.run();
     println!("Found {} Terraria servers!", found_servers.len());
 
     if start_time.elapsed().as_secs() > 60 * 60 * 2 {
         println!("Scan start was more than 2 hours ago, rescanning...");
-        scanner::clear();
+        scanner.clear();
         let rescan_ranges = found_servers
             .iter()
             .map(|s| s.address)
             .collect::<Vec<SocketAddrV4>>()
             .into();
-        scanner::synner(rescan_ranges, tcp.write);
-        found_servers = scanner::get_found_servers();
+        scanner.scan(&rescan_ranges, &ClientVersion::default());
+        found_servers = scanner.found_servers();
     }
+
+    if args.probe_versions {
+        found_servers = probe_versions(&mut scanner);
+    }
+    if args.probe_mods {
+        found_servers = probe_mods(&mut scanner);
+    }
+
+    let lines = found_servers.iter().map(results::format_line);
+    write_results(lines);
+    // The scan is done, there's nothing to resume anymore
+    let _ = remove_file(checkpoint_path);
+}
+
+/// `merge <files...>`: Combines the results files of the shards of a scan into one
+fn merge(files: &[String]) {
+    assert!(!files.is_empty(), "No results files specified");
+    let contents = files
+        .iter()
+        .map(|f| read_to_string(f).unwrap_or_else(|e| panic!("Failed to read {f}: {e}")))
+        .collect::<Vec<String>>();
+    let lines = results::merge(contents.iter().map(String::as_str));
+    info!("Merged {} files into {} servers", files.len(), lines.len());
+    write_results(lines);
+}
+
+fn write_results<T: AsRef<str>>(lines: impl IntoIterator<Item = T>) {
     let file_name = format!(
         "chlorophyte_mass_finder_results-{}.txt",
         Local::now().format("%y-%m-%d_%H_%M_%S")
     );
     let mut f = File::create(&file_name).expect("Failed to open files");
-    for s in &found_servers {
-        f.write_all(format!("{} {:?}\n", s.address, s.connection_request_result).as_bytes())
+    for line in lines {
+        f.write_all(format!("{}\n", line.as_ref()).as_bytes())
             .expect("Failed to write line to file");
     }
 
     println!("Results written to {file_name}");
 }
+
+/// Reconnects to servers that booted us because of a version mismatch with older versions
+/// until one is accepted
+fn probe_versions(scanner: &mut Scanner) -> Vec<TerrariaServer> {
+    let mut found_servers = scanner.found_servers();
+    // Newest first, as most outdated servers are only a few versions behind
+    for version in VERSIONS.iter().rev().filter(|v| **v != LATEST) {
+        let mismatched = found_servers
+            .iter()
+            .filter(|s| s.connection_request_result.is_version_mismatch())
+            .map(|s| s.address)
+            .collect::<Vec<SocketAddrV4>>();
+        if mismatched.is_empty() {
+            break;
+        }
+        info!(
+            "Probing {} servers with Terraria {}",
+            mismatched.len(),
+            version.release
+        );
+        scanner.scan(
+            &mismatched.into(),
+            &ClientVersion::Vanilla(version.protocol),
+        );
+        found_servers = scanner.found_servers();
+    }
+    found_servers
+}
+
+/// Reconnects to tModLoader servers as a tModLoader client to get their mod list
+fn probe_mods(scanner: &mut Scanner) -> Vec<TerrariaServer> {
+    let mut found_servers = scanner.found_servers();
+    let mut tried = HashSet::new();
+    loop {
+        // The kick reason usually tells us the server's tModLoader version,
+        // so a second attempt with that version can succeed
+        let mut by_version: HashMap<String, Vec<SocketAddrV4>> = HashMap::new();
+        for s in &found_servers {
+            let Some(t) = s.tmodloader.as_ref().filter(|t| t.mods.is_empty()) else {
+                continue;
+            };
+            let version = t
+                .version
+                .clone()
+                .unwrap_or_else(|| cli::DEFAULT_TMODLOADER_VERSION.to_string());
+            if tried.insert((s.address, version.clone())) {
+                by_version.entry(version).or_default().push(s.address);
+            }
+        }
+        if by_version.is_empty() {
+            break;
+        }
+        for (version, addrs) in by_version {
+            info!("Probing {} servers with tModLoader v{version}", addrs.len());
+            scanner.scan(&addrs.into(), &ClientVersion::TModLoader(version));
+        }
+        found_servers = scanner.found_servers();
+    }
+    found_servers
+}
@@ -1,36 +1,94 @@
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::version::{ClientVersion, GameVersion};
+use serde::{Deserialize, Serialize};
 use std::net::SocketAddrV4;
 use std::time::Instant;
-#[derive(Clone, Debug)]
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ConnectionRequestResult {
     Approved,
     PasswordRequired,
+    /// The server runs a different version than the one we sent
+    VersionMismatch,
     Booted(/* Reason: */ String),
 }
 
-#[derive(Clone, Debug)]
+impl ConnectionRequestResult {
+    pub const fn is_version_mismatch(&self) -> bool {
+        matches!(self, Self::VersionMismatch)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TerrariaServer {
     pub address: SocketAddrV4,
     /// How the server reacted to the connection request
     pub connection_request_result: ConnectionRequestResult,
+    /// The version the server accepted, if it got past the version check
+    #[serde(with = "protocol")]
+    pub game_version: Option<GameVersion>,
+    /// Set if the server runs tModLoader
+    pub tmodloader: Option<TModLoaderInfo>,
+}
+
+impl TerrariaServer {
+    /// Whether reconnecting with another client version could tell us more about the server
+    pub fn is_incomplete(&self) -> bool {
+        self.connection_request_result.is_version_mismatch()
+            || self.tmodloader.as_ref().is_some_and(|t| t.mods.is_empty())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TModLoaderInfo {
+    /// The tModLoader version, if the server told us
+    pub version: Option<String>,
+    /// The mods a client needs to join (`<name> v<version>`), if we got the mod list
+    pub mods: Vec<String>,
+}
+
+/// Saves game versions as their protocol version
+mod protocol {
+    use chlorophyte_terraria_protocol::version::{self, GameVersion};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[allow(clippy::ref_option)]
+    pub fn serialize<S: Serializer>(v: &Option<GameVersion>, s: S) -> Result<S::Ok, S::Error> {
+        v.map(|v| v.protocol).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<GameVersion>, D::Error> {
+        Ok(Option::<u32>::deserialize(d)?.and_then(version::by_protocol))
+    }
 }
 
+/// A server that answered our SYN
 #[derive(Clone, Debug)]
 pub struct ConnectionState {
-    /// When we sent the SYN - used for garbage collection
-    pub syn_time: Instant,
+    /// When we got the SYN+ACK - used for garbage collection
+    pub started: Instant,
     /// Whether the TCP handshake is complete (SYN, SYN+ACK, ACK)
     /// and we sent a connection request packet
     pub handshake_done: bool,
     /// Whether the connection was finished or reset
     pub closed: bool,
+    /// The sequence number of the next byte we expect from the server
+    pub next_sequence: u32,
+    /// Reassembles the Terraria packets sent by the server
+    pub decoder: TerrariaFrameDecoder,
+    /// What we identify as in the connection request
+    pub client_version: ClientVersion,
 }
 
-impl Default for ConnectionState {
-    fn default() -> Self {
+impl ConnectionState {
+    pub fn new(client_version: ClientVersion) -> Self {
         Self {
+            started: Instant::now(),
             handshake_done: false,
-            syn_time: Instant::now(),
             closed: false,
+            next_sequence: 0,
+            decoder: TerrariaFrameDecoder::new(),
+            client_version,
         }
     }
 }
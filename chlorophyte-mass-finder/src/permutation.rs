@@ -0,0 +1,65 @@
+//! Visits the targets of a scan in a pseudo-random order, so no network gets a burst of SYNs.
+//! This is the same idea as masscan's "blackrock": a Feistel network shuffles `0..a * b`,
+//! where `a * b` is just above the number of targets, and anything that lands outside the
+//! targets is shuffled again until it doesn't ("cycle walking")
+
+/// How often the halves are mixed. Masscan uses the same amount
+const ROUNDS: u64 = 14;
+
+/// A keyed permutation of `0..range`
+#[derive(Clone, Copy, Debug)]
+pub struct Permutation {
+    range: u64,
+    a: u64,
+    b: u64,
+    seed: u64,
+}
+
+impl Permutation {
+    pub fn new(range: u64, seed: u64) -> Self {
+        let root = range.isqrt();
+        let a = root.saturating_sub(2).max(1);
+        let mut b = root + 3;
+        while a * b <= range {
+            b += 1;
+        }
+        Self { range, a, b, seed }
+    }
+
+    /// Where the `index`th target is in the shuffled order. `index` has to be in the range
+    pub fn shuffle(&self, index: u64) -> u64 {
+        debug_assert!(index < self.range);
+        let mut shuffled = self.encrypt(index);
+        while shuffled >= self.range {
+            shuffled = self.encrypt(shuffled);
+        }
+        shuffled
+    }
+
+    /// One pass of the Feistel network, which is a permutation of `0..a * b`
+    fn encrypt(&self, m: u64) -> u64 {
+        let (mut left, mut right) = (m % self.a, m / self.a);
+        for round in 1..=ROUNDS {
+            let modulus = if round % 2 == 1 { self.a } else { self.b };
+            let mixed = (left + self.round_function(round, right) % modulus) % modulus;
+            left = right;
+            right = mixed;
+        }
+        if ROUNDS % 2 == 1 {
+            self.a * left + right
+        } else {
+            self.a * right + left
+        }
+    }
+
+    /// Splitmix64, so the order for a seed never changes between builds
+    const fn round_function(&self, round: u64, value: u64) -> u64 {
+        let mut z = self
+            .seed
+            .wrapping_add(round.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            ^ value;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
@@ -0,0 +1,40 @@
+//! The results file: one found server per line, starting with its address
+
+use crate::model::TerrariaServer;
+use std::collections::BTreeMap;
+use std::net::SocketAddrV4;
+
+/// The line of a server in the results file, without the line break
+pub fn format_line(server: &TerrariaServer) -> String {
+    let version = server.game_version.map_or("unknown", |v| v.release);
+    let tmodloader = server.tmodloader.as_ref().map_or_else(String::new, |t| {
+        format!(
+            " tModLoader {} mods: [{}]",
+            t.version.as_deref().unwrap_or("unknown"),
+            t.mods.join(", ")
+        )
+    });
+    format!(
+        "{} {:?} {version}{tmodloader}",
+        server.address, server.connection_request_result
+    )
+}
+
+/// Combines the contents of several results files, like the ones of the shards of a scan.
+/// Every address is kept once, with its line from the last file it's in,
+/// and the lines are sorted by address
+pub fn merge<'a>(files: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut lines = BTreeMap::new();
+    for file in files {
+        for line in file.lines() {
+            let address = line
+                .split_whitespace()
+                .next()
+                .map(str::parse::<SocketAddrV4>);
+            if let Some(Ok(address)) = address {
+                lines.insert(address, line);
+            }
+        }
+    }
+    lines.into_values().collect()
+}
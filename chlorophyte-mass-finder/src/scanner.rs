@@ -1,90 +1,390 @@
-use crate::model::{ConnectionRequestResult, ConnectionState, TerrariaServer};
+use crate::checkpoint::{config_hash, Checkpoint, CheckpointMismatch};
+use crate::cookie::SynCookies;
+use crate::model::{ConnectionRequestResult, ConnectionState, TModLoaderInfo, TerrariaServer};
+use crate::permutation::Permutation;
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::net_module::NetModule;
 use chlorophyte_terraria_protocol::packet::{
-    C2SConnect, ReadTerrariaPacket, S2CConnectionApproved, S2CFatalError, S2CPasswordRequired,
-    WriteTerrariaPacket,
+    find_tmodloader_version, C2SConnect, WriteTerrariaPacket,
 };
-use ipnet::IpAdd;
-use log::{info, trace};
+use chlorophyte_terraria_protocol::version::{self, ClientVersion};
+use chlorophyte_terraria_protocol::view::S2CPacketView;
+use log::{debug, info, trace, warn};
 use matscan_ranges::targets::ScanRanges;
-use matscan_tcp::{StatelessTcpReadHalf, StatelessTcpWriteHalf, Throttler};
-use once_cell::sync::Lazy;
+use matscan_tcp::{
+    SourcePort, StatelessTcp, StatelessTcpReadHalf, StatelessTcpWriteHalf, Throttler,
+};
 use pnet_packet::tcp::TcpFlags;
 use std::collections::HashMap;
-use std::env::args;
-use std::io::Cursor;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::mem::take;
 use std::net::SocketAddrV4;
-use std::sync::RwLock;
-use std::thread::sleep;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{sleep, spawn};
 use std::time::{Duration, Instant};
 
-static CONNECTIONS: Lazy<RwLock<HashMap<SocketAddrV4, ConnectionState>>> =
-    Lazy::new(|| RwLock::new(HashMap::new()));
-static FOUND_SERVERS: Lazy<RwLock<Vec<TerrariaServer>>> = Lazy::new(|| RwLock::new(vec![]));
-
-/// The thread that spews SYN packets
-#[allow(clippy::needless_pass_by_value, clippy::cast_precision_loss)]
-pub fn synner(ranges: ScanRanges, mut tcp_w: StatelessTcpWriteHalf) {
-    let max_pps = args().nth(2).map_or(50_000, |pps| {
-        pps.parse().expect("Failed to parse max pps as u64")
-    });
-    let addrs = ranges.count() as f64;
-    let mut throttler = Throttler::new(max_pps);
-    info!("Throttler is set to {max_pps} packets/s");
-
-    let mut t = Instant::now();
-    let mut p = 0usize;
-
-    let mut batch_size = throttler.next_batch();
-    let mut syns = 0f64;
-    for range in ranges.ranges() {
-        let mut addr = range.addr_start;
-        let addr_end = range.addr_end;
-        while addr <= addr_end {
-            for port in range.port_start..=range.port_end {
-                let addr = SocketAddrV4::new(addr, port);
-                tcp_w.send_syn(addr, fastrand::u32(..u32::MAX - 100_000));
-                p += 1;
-                syns += 1.;
-                if t.elapsed().as_nanos() >= Duration::from_secs(1).as_nanos() {
-                    info!("Scanning @ ~{p} packets/s");
-                    info!(
-                        "{:.3}% done ({}/{} hosts done) - found {} servers",
-                        (syns / addrs) * 100.,
-                        syns,
-                        addrs,
-                        FOUND_SERVERS.read().unwrap().len(),
-                    );
-                    t = Instant::now();
-                    p = 0;
-                }
-                batch_size -= 1;
-                if batch_size == 0 {
-                    batch_size = throttler.next_batch();
+/// What to scan and how
+#[derive(Clone)]
+pub struct ScanConfig {
+    /// The addresses and ports to scan
+    pub ranges: ScanRanges,
+    /// The maximum amount of SYNs sent per second
+    pub max_pps: u64,
+    /// The ports we send the SYNs from. The OS has to ignore them, see the README.
+    /// Scanners running at the same time need ranges that don't overlap, or they reset each
+    /// other's connections
+    pub source_port: SourcePort,
+    /// How long a server gets to answer the connection request after the handshake
+    pub connection_timeout: Duration,
+    /// How long we wait for answers after the last SYN was sent
+    pub linger: Duration,
+    /// What we identify as in the connection request
+    pub client_version: ClientVersion,
+    /// Decides the order the targets are visited in. The same seed gives the same order
+    pub seed: u64,
+    /// The part of the targets [`Scanner::run`] scans
+    pub shard: Shard,
+    /// Where [`Scanner::run`] saves how far it got, to resume the scan from there
+    pub checkpoint: Option<PathBuf>,
+    pub checkpoint_interval: Duration,
+}
+
+impl ScanConfig {
+    pub fn new(ranges: ScanRanges) -> Self {
+        Self {
+            ranges,
+            max_pps: 50_000,
+            source_port: SourcePort::Range {
+                min: 61000,
+                max: 65000,
+            },
+            connection_timeout: Duration::from_secs(7),
+            linger: Duration::from_secs(3),
+            client_version: ClientVersion::default(),
+            seed: fastrand::u64(..),
+            shard: Shard::ALL,
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// A part of the targets, for splitting a scan between machines. Shard `n` of `m` gets every
+/// `m`th target of the shuffled order, starting at the `n`th, so with the same seed the shards
+/// never overlap and together cover every target
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    index: u64,
+    count: u64,
+}
+
+impl Shard {
+    /// Everything
+    pub const ALL: Self = Self { index: 1, count: 1 };
+
+    /// Shard `index` of `count`, starting at 1. `None` if there's no such shard
+    pub const fn new(index: u64, count: u64) -> Option<Self> {
+        if index >= 1 && index <= count {
+            Some(Self { index, count })
+        } else {
+            None
+        }
+    }
+
+    /// Starts at 1
+    pub const fn index(self) -> u64 {
+        self.index
+    }
+
+    pub const fn count(self) -> u64 {
+        self.count
+    }
+
+    /// How many of `targets` targets belong to this shard
+    pub const fn len(self, targets: u64) -> u64 {
+        (targets + self.count - self.index) / self.count
+    }
+
+    /// The positions in the shuffled order of `targets` targets that belong to this shard
+    pub fn positions(self, targets: u64) -> impl Iterator<Item = u64> {
+        // Both fit into a usize, there are never more targets than that
+        #[allow(clippy::cast_possible_truncation)]
+        (self.index - 1..targets).step_by(self.count as usize)
+    }
+}
+
+impl FromStr for Shard {
+    type Err = ();
+
+    /// Parses `<n>/<m>`, like `1/4`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, count) = s.split_once('/').ok_or(())?;
+        let index = index.parse().map_err(|_| ())?;
+        let count = count.parse().map_err(|_| ())?;
+        Self::new(index, count).ok_or(())
+    }
+}
+
+impl Display for Shard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// The state shared between the scanner and its receiver and garbage collector threads
+struct State {
+    /// Only the servers that answered our SYN, so this doesn't grow with the size of the scan
+    connections: RwLock<HashMap<SocketAddrV4, ConnectionState>>,
+    cookies: SynCookies,
+    /// What we identify as in the connection requests of the current scan
+    client_version: RwLock<ClientVersion>,
+    found_servers: RwLock<Vec<TerrariaServer>>,
+    /// Gets every server we find, and every server again when probing it told us more
+    results: Sender<TerrariaServer>,
+    /// Cleared when the scanner is dropped, which stops the threads
+    running: AtomicBool,
+    seed: u64,
+    config_hash: u64,
+    /// The next position in the shuffled order [`Scanner::run`] sends a SYN to
+    position: AtomicU64,
+    /// How far the positions of the targets that may still answer reach back from `position`
+    unanswered: u64,
+    /// Held while a checkpoint is saved, so two saves never write the file at once
+    checkpoint_lock: Mutex<()>,
+}
+
+/// Looks at the servers a [`Scanner`] found so far, from any thread
+#[derive(Clone)]
+pub struct ScanHandle(Arc<State>);
+
+impl ScanHandle {
+    pub fn found_servers(&self) -> Vec<TerrariaServer> {
+        self.0.found_servers.read().unwrap().clone()
+    }
+
+    /// How far [`Scanner::run`] got and what it found. The position is set back by the SYNs
+    /// sent within the connection timeout and linger, as their answers may still be coming
+    pub fn checkpoint(&self) -> Checkpoint {
+        let position = self.0.position.load(Ordering::Relaxed);
+        Checkpoint {
+            seed: self.0.seed,
+            config_hash: self.0.config_hash,
+            position: position.saturating_sub(self.0.unanswered),
+            found_servers: self.found_servers(),
+        }
+    }
+
+    /// Saves [`ScanHandle::checkpoint`] to `path`, after any other save of this scan finished
+    pub fn save_checkpoint(&self, path: &Path) -> io::Result<()> {
+        let _saving = self.0.checkpoint_lock.lock().unwrap();
+        self.checkpoint().save(path)
+    }
+}
+
+pub struct Scanner {
+    config: ScanConfig,
+    tcp_w: StatelessTcpWriteHalf,
+    state: Arc<State>,
+}
+
+impl Scanner {
+    /// Opens the raw sockets and starts the threads that receive and clean up connections.
+    /// The receiver gets every server as it's found. Packets to ports outside
+    /// [`ScanConfig::source_port`] are ignored, but every other one this scanner didn't
+    /// open a connection for is reset, so other scanners have to use other ports
+    pub fn new(config: ScanConfig) -> (Self, Receiver<TerrariaServer>) {
+        let (tx, rx) = channel();
+        // Every SYN sent within that time may still turn into a server
+        let answer_time = (config.connection_timeout + config.linger).as_secs_f64();
+        let unanswered = (config.max_pps as f64 * answer_time) as u64;
+        let state = Arc::new(State {
+            connections: RwLock::new(HashMap::new()),
+            cookies: SynCookies::new(),
+            client_version: RwLock::new(config.client_version.clone()),
+            found_servers: RwLock::new(vec![]),
+            results: tx,
+            running: AtomicBool::new(true),
+            seed: config.seed,
+            config_hash: config_hash(&config.ranges, config.seed, config.shard),
+            position: AtomicU64::new(0),
+            unanswered: unanswered.saturating_mul(config.shard.count()),
+            checkpoint_lock: Mutex::new(()),
+        });
+        let tcp = StatelessTcp::new(config.source_port);
+        let (tcp_w, tcp_r) = (tcp.write.clone(), tcp.read);
+        let receiver_state = state.clone();
+        spawn(move || receiver(&receiver_state, tcp_w, tcp_r));
+        let gc_state = state.clone();
+        let timeout = config.connection_timeout;
+        spawn(move || garbage_collector(&gc_state, timeout));
+        let scanner = Self {
+            config,
+            tcp_w: tcp.write,
+            state,
+        };
+        (scanner, rx)
+    }
+
+    pub const fn config(&self) -> &ScanConfig {
+        &self.config
+    }
+
+    /// Scans the configured shard of the configured ranges and returns everything found so far.
+    /// Saves a checkpoint every now and then and at the end, if there's a checkpoint file
+    pub fn run(&mut self) -> Vec<TerrariaServer> {
+        let ranges = self.config.ranges.clone();
+        let client_version = self.config.client_version.clone();
+        self.scan_shard(&ranges, &client_version, self.config.shard, true);
+        self.save_checkpoint();
+        self.found_servers()
+    }
+
+    /// Makes [`Scanner::run`] continue where the checkpoint was saved,
+    /// with the servers that were found before
+    pub fn resume(&mut self, checkpoint: Checkpoint) -> Result<(), CheckpointMismatch> {
+        if checkpoint.config_hash != self.state.config_hash {
+            return Err(CheckpointMismatch);
+        }
+        info!(
+            "Resuming at position {} with {} servers found",
+            checkpoint.position,
+            checkpoint.found_servers.len()
+        );
+        self.state
+            .position
+            .store(checkpoint.position, Ordering::Relaxed);
+        *self.state.found_servers.write().unwrap() = checkpoint.found_servers;
+        Ok(())
+    }
+
+    fn save_checkpoint(&self) {
+        let Some(path) = &self.config.checkpoint else {
+            return;
+        };
+        if let Err(e) = self.handle().save_checkpoint(path) {
+            warn!("Failed to save checkpoint to {}: {e}", path.display());
+        }
+    }
+
+    /// Sends SYNs to all of `ranges` in a shuffled order and waits for the late answers.
+    /// The servers will be sent a connection request as `client_version`
+    pub fn scan(&mut self, ranges: &ScanRanges, client_version: &ClientVersion) {
+        self.scan_shard(ranges, client_version, Shard::ALL, false);
+    }
+
+    /// With `checkpoint`, starts at the saved position and keeps it up to date
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn scan_shard(
+        &mut self,
+        ranges: &ScanRanges,
+        client_version: &ClientVersion,
+        shard: Shard,
+        checkpoint: bool,
+    ) {
+        *self.state.client_version.write().unwrap() = client_version.clone();
+        let max_pps = self.config.max_pps;
+        let count = ranges.count() as u64;
+        let addrs = shard.len(count) as f64;
+        let mut throttler = Throttler::new(max_pps);
+        info!("Throttler is set to {max_pps} packets/s");
+
+        let mut t = Instant::now();
+        let mut p = 0usize;
+
+        let start = if checkpoint {
+            self.state.position.load(Ordering::Relaxed)
+        } else {
+            0
+        };
+        let mut last_checkpoint = Instant::now();
+
+        let mut batch_size = throttler.next_batch();
+        let mut syns = shard.len(start.min(count)) as f64;
+        let targets = ranges.clone().to_static();
+        let permutation = Permutation::new(count, self.config.seed);
+        for i in shard.positions(count).skip(syns as usize) {
+            let addr = targets.index(permutation.shuffle(i) as usize);
+            let source_port = self.config.source_port.pick(fastrand::u32(..));
+            let sequence = self.state.cookies.sequence(addr, source_port);
+            self.tcp_w.send_syn_from(addr, source_port, sequence);
+            p += 1;
+            syns += 1.;
+            if t.elapsed().as_nanos() >= Duration::from_secs(1).as_nanos() {
+                info!("Scanning @ ~{p} packets/s");
+                info!(
+                    "{:.3}% done ({}/{} hosts done) - found {} servers",
+                    (syns / addrs) * 100.,
+                    syns,
+                    addrs,
+                    self.state.found_servers.read().unwrap().len(),
+                );
+                t = Instant::now();
+                p = 0;
+            }
+            batch_size -= 1;
+            if batch_size == 0 {
+                batch_size = throttler.next_batch();
+            }
+            if checkpoint {
+                self.state.position.store(i + 1, Ordering::Relaxed);
+                if last_checkpoint.elapsed() >= self.config.checkpoint_interval {
+                    self.save_checkpoint();
+                    last_checkpoint = Instant::now();
                 }
-                CONNECTIONS
-                    .write()
-                    .unwrap()
-                    .insert(addr, ConnectionState::default());
             }
-            addr = addr.saturating_add(1);
         }
+        info!("SYNner done! Waiting {:?} for answers", self.config.linger);
+        sleep(self.config.linger);
+    }
+
+    pub fn found_servers(&self) -> Vec<TerrariaServer> {
+        self.handle().found_servers()
+    }
+
+    pub fn handle(&self) -> ScanHandle {
+        ScanHandle(self.state.clone())
+    }
+
+    /// Forgets all servers and connections, so they can be scanned again
+    pub fn clear(&self) {
+        self.state.found_servers.write().unwrap().clear();
+        self.state.connections.write().unwrap().clear();
+    }
+}
+
+impl Drop for Scanner {
+    fn drop(&mut self) {
+        self.state.running.store(false, Ordering::Relaxed);
     }
 }
 
 /// The thread that finishes the TCP handshake and handles incoming packets from the server
-#[allow(clippy::significant_drop_tightening, clippy::too_many_lines)]
-pub fn receiver(mut tcp_w: StatelessTcpWriteHalf, mut tcp_r: StatelessTcpReadHalf) -> ! {
-    let mut conn_request_packet = vec![0u8; 0];
-    conn_request_packet
-        .write_terraria_packet(C2SConnect { version: 279 })
-        .unwrap();
-    loop {
+#[allow(clippy::significant_drop_tightening, clippy::needless_pass_by_value)]
+fn receiver(state: &State, mut tcp_w: StatelessTcpWriteHalf, mut tcp_r: StatelessTcpReadHalf) {
+    while state.running.load(Ordering::Relaxed) {
         let Some((ipv4, tcp)) = tcp_r.recv() else {
             sleep(Duration::from_millis(2));
             continue;
         };
         let addr = SocketAddrV4::new(ipv4.source, tcp.source);
-        let mut wguard = CONNECTIONS.write().unwrap();
+        let syn_ack = tcp.flags & TcpFlags::SYN != 0 && tcp.flags & TcpFlags::ACK != 0;
+        let mut wguard = state.connections.write().unwrap();
+        // The first SYN+ACK of a server is the only thing that creates a connection,
+        // and only if it acknowledges a SYN we sent
+        if syn_ack
+            && !wguard.contains_key(&addr)
+            && state
+                .cookies
+                .check(addr, tcp.destination, tcp.acknowledgement)
+        {
+            let client_version = state.client_version.read().unwrap().clone();
+            wguard.insert(addr, ConnectionState::new(client_version));
+        }
         let Some(conn) = wguard.get_mut(&addr) else {
             tcp_w.send_rst(addr, tcp.destination, tcp.acknowledgement, tcp.sequence);
             continue;
@@ -95,84 +395,82 @@ pub fn receiver(mut tcp_w: StatelessTcpWriteHalf, mut tcp_r: StatelessTcpReadHal
         }
 
         // SYN+ACK
-        if tcp.flags & TcpFlags::SYN != 0 && tcp.flags & TcpFlags::ACK != 0 {
-            tcp_w.send_ack(addr, tcp.destination, tcp.acknowledgement, tcp.sequence + 1);
+        if syn_ack {
+            conn.next_sequence = tcp.sequence.wrapping_add(1);
+            let mut conn_request_packet = vec![0u8; 0];
+            // Not every version we are told to connect as fits into a packet
+            if conn_request_packet
+                .write_terraria_packet(C2SConnect {
+                    version: conn.client_version.clone(),
+                })
+                .is_err()
+            {
+                tcp_w.send_rst(addr, tcp.destination, tcp.acknowledgement, tcp.sequence);
+                conn.closed = true;
+                continue;
+            }
+            tcp_w.send_ack(
+                addr,
+                tcp.destination,
+                tcp.acknowledgement,
+                conn.next_sequence,
+            );
             tcp_w.send_data(
                 addr,
                 tcp.destination,
                 tcp.acknowledgement,
-                tcp.sequence + 1,
+                conn.next_sequence,
                 &conn_request_packet,
             );
             conn.handshake_done = true;
         }
 
-        // PSH - Terraria packet
-        if tcp.flags & TcpFlags::PSH != 0 {
+        // Data - one or more (possibly partial) Terraria packets
+        if conn.handshake_done && !tcp.payload.is_empty() {
+            // Out of order or retransmitted - ask for the bytes we are missing
+            if tcp.sequence != conn.next_sequence {
+                tcp_w.send_ack(
+                    addr,
+                    tcp.destination,
+                    tcp.acknowledgement,
+                    conn.next_sequence,
+                );
+                continue;
+            }
+            conn.next_sequence = tcp
+                .sequence
+                .wrapping_add(u32::try_from(tcp.payload.len()).unwrap());
             tcp_w.send_ack(
                 addr,
                 tcp.destination,
                 tcp.acknowledgement,
-                tcp.sequence + u32::try_from(tcp.payload.len()).unwrap(),
+                conn.next_sequence,
             );
-            let Some(packet_id) = tcp.payload.get(2).copied() else {
-                tcp_w.send_rst(addr, tcp.destination, tcp.acknowledgement, tcp.sequence);
+            conn.decoder.feed(&tcp.payload);
+
+            // Reading the answer renders kick reasons, which shouldn't keep the other threads
+            // away from the connections
+            let client_version = conn.client_version.clone();
+            let mut decoder = take(&mut conn.decoder);
+            drop(wguard);
+            let answer = read_answer(addr, &client_version, &mut decoder);
+            wguard = state.connections.write().unwrap();
+            let Some(conn) = wguard.get_mut(&addr) else {
                 continue;
             };
-            if FOUND_SERVERS
-                .read()
-                .unwrap()
-                .iter()
-                .any(|s| s.address == addr)
-            {
-                continue;
-            }
-            match packet_id {
-                2 => {
-                    if let Ok(packet) =
-                        Cursor::new(&tcp.payload).read_terraria_packet::<S2CFatalError>()
-                    {
-                        info!("Found server, but I got booted: {addr}");
-                        FOUND_SERVERS.write().unwrap().push(TerrariaServer {
-                            address: addr,
-                            connection_request_result: ConnectionRequestResult::Booted(
-                                packet.error,
-                            ),
-                        });
-                    }
-                }
-                3 if Cursor::new(&tcp.payload)
-                    .read_terraria_packet::<S2CConnectionApproved>()
-                    .is_ok() =>
-                {
-                    info!("Found server: {addr}");
-                    FOUND_SERVERS.write().unwrap().push(TerrariaServer {
-                        address: addr,
-                        connection_request_result: ConnectionRequestResult::Approved,
-                    });
-                }
-                9 => {
-                    info!("Found server: {addr}");
-                    FOUND_SERVERS.write().unwrap().push(TerrariaServer {
-                        address: addr,
-                        connection_request_result: ConnectionRequestResult::Approved,
-                    });
-                }
-                37 if Cursor::new(&tcp.payload)
-                    .read_terraria_packet::<S2CPasswordRequired>()
-                    .is_ok() =>
-                {
-                    info!("Found password-protected server: {addr}");
-                    FOUND_SERVERS.write().unwrap().push(TerrariaServer {
-                        address: addr,
-                        connection_request_result: ConnectionRequestResult::PasswordRequired,
-                    });
+            conn.decoder = decoder;
+            if let Answer::Done(server) = answer {
+                tcp_w.send_rst(addr, tcp.destination, tcp.acknowledgement, tcp.sequence);
+                conn.closed = true;
+                if let Some(server) = server {
+                    record(state, server);
                 }
-                82 => continue,
-                _ => {}
+                continue;
             }
-            tcp_w.send_rst(addr, tcp.destination, tcp.acknowledgement, tcp.sequence);
         }
+        let Some(conn) = wguard.get_mut(&addr) else {
+            continue;
+        };
 
         // RST
         if tcp.flags & TcpFlags::RST != 0 {
@@ -187,32 +485,159 @@ pub fn receiver(mut tcp_w: StatelessTcpWriteHalf, mut tcp_r: StatelessTcpReadHal
     }
 }
 
-/// Removes connections that didn't send a SYN+ACK or were reset
-pub fn garbage_collector() -> ! {
-    let timeout = Duration::from_secs(7);
+/// What the packets a server sent so far tell us
+#[derive(Debug)]
+pub enum Answer {
+    /// The server didn't answer the connection request yet
+    Pending,
+    /// The server answered and the connection can be closed.
+    /// `None` if it sent something a Terraria server wouldn't
+    Done(Option<TerrariaServer>),
+}
+
+/// Reads the complete packets in `decoder`, until one of them answers our connection request
+pub fn read_answer(
+    addr: SocketAddrV4,
+    client_version: &ClientVersion,
+    decoder: &mut TerrariaFrameDecoder,
+) -> Answer {
     loop {
-        let conns = CONNECTIONS.read().unwrap().clone();
-        let mut to_remove = vec![];
-        conns
-            .iter()
-            .enumerate()
-            .filter(|c| c.1 .1.syn_time.elapsed() > timeout || c.1 .1.closed)
-            .for_each(|c| to_remove.push(c.1 .0));
-        if !to_remove.is_empty() {
-            trace!("[gc] removing {} connections", to_remove.len());
-        }
-        for i in to_remove {
-            CONNECTIONS.write().unwrap().remove(i);
+        match decoder.next_view() {
+            Ok(Some(packet)) => {
+                if let Answer::Done(server) = handle_packet(addr, client_version, packet) {
+                    return Answer::Done(server);
+                }
+            }
+            Ok(None) => return Answer::Pending,
+            Err(_) => return Answer::Done(None),
         }
-        sleep(Duration::from_millis(40));
     }
 }
 
-pub fn get_found_servers() -> Vec<TerrariaServer> {
-    FOUND_SERVERS.read().unwrap().clone()
+fn handle_packet(
+    addr: SocketAddrV4,
+    client_version: &ClientVersion,
+    packet: S2CPacketView,
+) -> Answer {
+    let mut tmodloader = match client_version {
+        ClientVersion::Vanilla(_) => None,
+        ClientVersion::TModLoader(version) => Some(TModLoaderInfo {
+            version: Some(version.clone()),
+            mods: vec![],
+        }),
+    };
+    let connection_request_result = match packet {
+        S2CPacketView::FatalError(packet) => {
+            if packet.is_tmodloader() {
+                let reason = packet.error.render();
+                tmodloader = Some(TModLoaderInfo {
+                    version: find_tmodloader_version(&reason),
+                    mods: vec![],
+                });
+                ConnectionRequestResult::Booted(reason)
+            } else if packet.is_version_mismatch() {
+                ConnectionRequestResult::VersionMismatch
+            } else {
+                ConnectionRequestResult::Booted(packet.error.render())
+            }
+        }
+        S2CPacketView::ConnectionApproved(_) | S2CPacketView::StatusText(_) => {
+            ConnectionRequestResult::Approved
+        }
+        S2CPacketView::PasswordRequired(_) => ConnectionRequestResult::PasswordRequired,
+        S2CPacketView::SyncMods(packet) => {
+            let t = tmodloader.get_or_insert(TModLoaderInfo {
+                version: None,
+                mods: vec![],
+            });
+            t.mods = packet
+                .mods
+                .iter()
+                .map(|m| format!("{} v{}", m.name, m.version))
+                .collect();
+            ConnectionRequestResult::Approved
+        }
+        S2CPacketView::NetModule(packet) => {
+            // Some servers greet everyone in chat, even before they're approved
+            if let Ok(NetModule::Text { text, .. }) =
+                NetModule::deserialize(packet.module_id, packet.body)
+            {
+                debug!("{addr} says: {text}");
+            }
+            return Answer::Pending;
+        }
+        S2CPacketView::Other { .. } => return Answer::Done(None),
+    };
+    let game_version = match (&connection_request_result, client_version) {
+        (
+            ConnectionRequestResult::Approved | ConnectionRequestResult::PasswordRequired,
+            ClientVersion::Vanilla(protocol),
+        ) => version::by_protocol(*protocol),
+        _ => None,
+    };
+    Answer::Done(Some(TerrariaServer {
+        address: addr,
+        connection_request_result,
+        game_version,
+        tmodloader,
+    }))
+}
+
+/// Adds the server to the found servers, or updates it if we were probing it
+fn record(state: &State, server: TerrariaServer) {
+    let addr = server.address;
+    let mut found_servers = state.found_servers.write().unwrap();
+    match found_servers.iter_mut().find(|s| s.address == addr) {
+        // We are probing a server that booted us with another client version
+        Some(s) if s.is_incomplete() => {
+            if let Some(version) = server.game_version {
+                info!("Server {addr} runs Terraria {}", version.release);
+            }
+            if let Some(t) = server.tmodloader.as_ref().filter(|t| !t.mods.is_empty()) {
+                info!("Server {addr} runs tModLoader with {} mods", t.mods.len());
+            }
+            *s = server.clone();
+        }
+        Some(_) => return,
+        None => {
+            match server.connection_request_result {
+                _ if server.tmodloader.is_some() => info!("Found tModLoader server: {addr}"),
+                ConnectionRequestResult::Approved => info!("Found server: {addr}"),
+                ConnectionRequestResult::PasswordRequired => {
+                    info!("Found password-protected server: {addr}");
+                }
+                ConnectionRequestResult::VersionMismatch => {
+                    info!("Found server running another version: {addr}");
+                }
+                ConnectionRequestResult::Booted(_) => {
+                    info!("Found server, but I got booted: {addr}");
+                }
+            }
+            found_servers.push(server.clone());
+        }
+    }
+    // Nobody has to listen
+    let _ = state.results.send(server);
 }
 
-pub fn clear() {
-    FOUND_SERVERS.write().unwrap().clear();
-    CONNECTIONS.write().unwrap().clear();
+/// Removes connections that were closed or took too long to answer the connection request
+fn garbage_collector(state: &State, timeout: Duration) {
+    while state.running.load(Ordering::Relaxed) {
+        let to_remove = state
+            .connections
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, c)| c.started.elapsed() > timeout || c.closed)
+            .map(|(addr, _)| *addr)
+            .collect::<Vec<SocketAddrV4>>();
+        if !to_remove.is_empty() {
+            trace!("[gc] removing {} connections", to_remove.len());
+            let mut connections = state.connections.write().unwrap();
+            for addr in to_remove {
+                connections.remove(&addr);
+            }
+        }
+        sleep(Duration::from_millis(40));
+    }
 }
@@ -0,0 +1,127 @@
+use chlorophyte_mass_finder::model::{ConnectionRequestResult, TerrariaServer};
+use chlorophyte_mass_finder::scanner::{read_answer, Answer};
+use chlorophyte_mock_server::{Behavior, MockConfig, MockServer};
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::packet::{C2SConnect, WriteTerrariaPacket};
+use chlorophyte_terraria_protocol::version::{ClientVersion, LATEST};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// Sends the connection request the scanner sends after the handshake
+/// and reads the answer like the scanner does
+fn probe(config: MockConfig, version: ClientVersion) -> TerrariaServer {
+    let SocketAddr::V4(addr) = MockServer::start(config).unwrap() else {
+        panic!("The mock server should listen on IPv4");
+    };
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut request = vec![];
+    request
+        .write_terraria_packet(C2SConnect {
+            version: version.clone(),
+        })
+        .unwrap();
+    stream.write_all(&request).unwrap();
+
+    let mut decoder = TerrariaFrameDecoder::new();
+    let mut buf = [0u8; 1460];
+    loop {
+        let n = stream.read(&mut buf).unwrap();
+        assert_ne!(n, 0, "The server disconnected without answering");
+        decoder.feed(&buf[..n]);
+        match read_answer(addr, &version, &mut decoder) {
+            Answer::Pending => {}
+            Answer::Done(server) => return server.expect("Expected a Terraria server"),
+        }
+    }
+}
+
+fn with_behavior(behavior: Behavior) -> MockConfig {
+    MockConfig {
+        behavior,
+        ..MockConfig::default()
+    }
+}
+
+#[test]
+fn vanilla() {
+    let server = probe(MockConfig::default(), ClientVersion::default());
+    assert!(matches!(
+        server.connection_request_result,
+        ConnectionRequestResult::Approved
+    ));
+    assert_eq!(server.game_version, Some(LATEST));
+    assert!(server.tmodloader.is_none());
+}
+
+#[test]
+fn password_and_kicks() {
+    let server = probe(
+        with_behavior(Behavior::PasswordProtected("hunter2".to_string())),
+        ClientVersion::default(),
+    );
+    assert!(matches!(
+        server.connection_request_result,
+        ConnectionRequestResult::PasswordRequired
+    ));
+
+    let server = probe(
+        with_behavior(Behavior::VersionMismatch),
+        ClientVersion::default(),
+    );
+    assert!(server.connection_request_result.is_version_mismatch());
+    assert_eq!(server.game_version, None);
+
+    let server = probe(
+        with_behavior(Behavior::Kick("You are banned.".to_string())),
+        ClientVersion::default(),
+    );
+    let ConnectionRequestResult::Booted(reason) = server.connection_request_result else {
+        panic!("Expected to be booted");
+    };
+    assert_eq!(reason, "You are banned.");
+}
+
+#[test]
+fn older_version() {
+    let config = MockConfig {
+        protocol: 248,
+        ..MockConfig::default()
+    };
+    let server = probe(config.clone(), ClientVersion::default());
+    assert!(server.is_incomplete());
+    let server = probe(config, ClientVersion::Vanilla(248));
+    assert_eq!(server.game_version.map(|v| v.release), Some("1.4.3.6"));
+}
+
+#[test]
+fn tmodloader() {
+    let config = with_behavior(Behavior::TModLoader {
+        version: "2023.8.3.4".to_string(),
+        mods: vec![("CalamityMod".to_string(), "2.0.3.1".to_string())],
+    });
+    let server = probe(config.clone(), ClientVersion::default());
+    let tmodloader = server.tmodloader.unwrap();
+    assert_eq!(tmodloader.version.as_deref(), Some("2023.8.3.4"));
+    assert!(tmodloader.mods.is_empty());
+
+    let version = ClientVersion::TModLoader("2023.8.3.4".to_string());
+    let server = probe(config, version);
+    assert_eq!(server.tmodloader.unwrap().mods, ["CalamityMod v2.0.3.1"]);
+}
+
+#[test]
+fn fragmented_answer() {
+    let config = MockConfig {
+        fragment_size: Some(1),
+        ..with_behavior(Behavior::Kick("You are not on the whitelist.".to_string()))
+    };
+    let server = probe(config, ClientVersion::default());
+    assert!(matches!(
+        server.connection_request_result,
+        ConnectionRequestResult::Booted(_)
+    ));
+}
@@ -0,0 +1,59 @@
+use chlorophyte_mass_finder::checkpoint::{config_hash, Checkpoint};
+use chlorophyte_mass_finder::model::{ConnectionRequestResult, TModLoaderInfo, TerrariaServer};
+use chlorophyte_mass_finder::Shard;
+use chlorophyte_terraria_protocol::version::LATEST;
+use matscan_ranges::targets::{ScanRange, ScanRanges};
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+#[test]
+fn checkpoints_are_saved_and_loaded() {
+    let checkpoint = Checkpoint {
+        seed: 42,
+        config_hash: 7,
+        position: 123_456,
+        found_servers: vec![
+            TerrariaServer {
+                address: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 7777),
+                connection_request_result: ConnectionRequestResult::Approved,
+                game_version: Some(LATEST),
+                tmodloader: None,
+            },
+            TerrariaServer {
+                address: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 7777),
+                connection_request_result: ConnectionRequestResult::Booted("Bye".to_string()),
+                game_version: None,
+                tmodloader: Some(TModLoaderInfo {
+                    version: Some("2023.8.3.4".to_string()),
+                    mods: vec!["CalamityMod v2.0.3.1".to_string()],
+                }),
+            },
+        ],
+    };
+    let path = std::env::temp_dir().join(format!(
+        "chlorophyte-checkpoint-{}.json",
+        std::process::id()
+    ));
+    checkpoint.save(&path).unwrap();
+    let loaded = Checkpoint::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.seed, 42);
+    assert_eq!(loaded.position, 123_456);
+    assert_eq!(
+        format!("{:?}", loaded.found_servers),
+        format!("{:?}", checkpoint.found_servers)
+    );
+}
+
+#[test]
+fn config_hash_covers_ranges_seed_and_shard() {
+    let mut ranges = ScanRanges::new();
+    ranges.extend(vec!["10.0.0.0/16:7777".parse::<ScanRange>().unwrap()]);
+    let mut other_ranges = ScanRanges::new();
+    other_ranges.extend(vec!["10.0.0.0/16:7778".parse::<ScanRange>().unwrap()]);
+    let hash = config_hash(&ranges, 42, Shard::ALL);
+    assert_eq!(hash, config_hash(&ranges.clone(), 42, Shard::ALL));
+    assert_ne!(hash, config_hash(&other_ranges, 42, Shard::ALL));
+    assert_ne!(hash, config_hash(&ranges, 43, Shard::ALL));
+    assert_ne!(hash, config_hash(&ranges, 42, "1/2".parse().unwrap()));
+}
@@ -0,0 +1,18 @@
+use chlorophyte_mass_finder::cookie::SynCookies;
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+#[test]
+fn syn_acks_are_checked_against_the_cookie() {
+    let cookies = SynCookies::new();
+    let addr = SocketAddrV4::new(Ipv4Addr::new(203, 0, 113, 7), 7777);
+    let sequence = cookies.sequence(addr, 61234);
+    assert_eq!(sequence, cookies.sequence(addr, 61234));
+    assert!(cookies.check(addr, 61234, sequence.wrapping_add(1)));
+
+    // Anything that doesn't answer exactly that SYN is ignored
+    assert!(!cookies.check(addr, 61234, sequence));
+    assert!(!cookies.check(addr, 61235, sequence.wrapping_add(1)));
+    let other = SocketAddrV4::new(Ipv4Addr::new(203, 0, 113, 8), 7777);
+    assert!(!cookies.check(other, 61234, sequence.wrapping_add(1)));
+    assert!(!SynCookies::new().check(addr, 61234, sequence.wrapping_add(1)));
+}
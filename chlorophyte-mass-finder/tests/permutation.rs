@@ -0,0 +1,29 @@
+use chlorophyte_mass_finder::permutation::Permutation;
+
+fn order(range: u64, seed: u64) -> Vec<u64> {
+    let permutation = Permutation::new(range, seed);
+    (0..range).map(|i| permutation.shuffle(i)).collect()
+}
+
+#[test]
+fn every_target_is_visited_once() {
+    for range in [0, 1, 2, 3, 7, 9, 100, 1000, 65_539] {
+        let mut visited = order(range, 42);
+        visited.sort_unstable();
+        assert_eq!(visited, (0..range).collect::<Vec<_>>(), "range {range}");
+    }
+}
+
+#[test]
+fn order_depends_on_the_seed() {
+    let shuffled = order(1000, 42);
+    assert_eq!(shuffled, order(1000, 42));
+    assert_ne!(shuffled, order(1000, 43));
+    assert_ne!(shuffled, (0..1000).collect::<Vec<_>>());
+    // Neighbours in the scan shouldn't be neighbours in the ranges
+    let close = shuffled
+        .windows(2)
+        .filter(|w| w[0].abs_diff(w[1]) < 10)
+        .count();
+    assert!(close < 50, "{close} of 999 steps are shorter than 10");
+}
@@ -0,0 +1,54 @@
+use chlorophyte_mass_finder::model::{ConnectionRequestResult, TerrariaServer};
+use chlorophyte_mass_finder::permutation::Permutation;
+use chlorophyte_mass_finder::results;
+use chlorophyte_mass_finder::Shard;
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+#[test]
+fn shards_split_the_targets() {
+    let targets = 1000;
+    let permutation = Permutation::new(targets, 42);
+    let mut scanned = vec![];
+    for index in 1..=3 {
+        let shard = format!("{index}/3").parse::<Shard>().unwrap();
+        let positions = shard.positions(targets).collect::<Vec<u64>>();
+        assert_eq!(positions.len() as u64, shard.len(targets));
+        scanned.extend(positions.into_iter().map(|p| permutation.shuffle(p)));
+    }
+    scanned.sort_unstable();
+    assert_eq!(scanned, (0..targets).collect::<Vec<u64>>());
+
+    assert_eq!("2/4".parse(), Ok(Shard::new(2, 4).unwrap()));
+    assert_eq!(Shard::new(0, 4), None);
+    assert_eq!(Shard::new(1, 0), None);
+    for invalid in ["0/4", "5/4", "1/0", "1", "a/b"] {
+        assert!(invalid.parse::<Shard>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn merge_results() {
+    let server = |last: u8| TerrariaServer {
+        address: SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, last), 7777),
+        connection_request_result: ConnectionRequestResult::Approved,
+        game_version: None,
+        tmodloader: None,
+    };
+    let first = format!(
+        "{}\n{}\n",
+        results::format_line(&server(2)),
+        results::format_line(&server(1))
+    );
+    let second = format!(
+        "{}\n\n10.0.0.2:7777 PasswordRequired 1.4.4.9\n",
+        results::format_line(&server(3))
+    );
+    assert_eq!(
+        results::merge([first.as_str(), second.as_str()]),
+        [
+            "10.0.0.1:7777 Approved unknown",
+            "10.0.0.2:7777 PasswordRequired 1.4.4.9",
+            "10.0.0.3:7777 Approved unknown",
+        ]
+    );
+}
@@ -0,0 +1,313 @@
+//! A fake Terraria server that answers like a real one would, so the other crates can be tested
+//! on loopback instead of against real hosts
+
+mod world;
+
+use chlorophyte_terraria_protocol::error;
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::net_module::SERVER_AUTHOR;
+use chlorophyte_terraria_protocol::network_text::NetworkText;
+use chlorophyte_terraria_protocol::packet::{
+    C2SPacket, Difficulty, ModInfo, S2CCompleteConnectionAndSpawn, S2CConnectionApproved,
+    S2CFatalError, S2CFinishedConnectingToServer, S2CNetModule, S2CPasswordRequired,
+    S2CPlayerActive, S2CPlayerInfo, S2CStatusText, S2CSyncMods, S2CTileFrameSection,
+    TerrariaPacket, WriteTerrariaPacket,
+};
+use chlorophyte_terraria_protocol::types::TerrariaTypesW;
+use chlorophyte_terraria_protocol::version::{ClientVersion, LATEST};
+use log::{debug, info};
+use std::io;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+
+pub use world::{SPAWN_X, SPAWN_Y};
+
+/// How the server treats clients that connect to it
+#[derive(Clone, Debug)]
+pub enum Behavior {
+    /// Lets everyone with the right protocol version in
+    Vanilla,
+    /// Asks for a password and kicks clients that send the wrong one
+    PasswordProtected(String),
+    /// Kicks everyone for not using the same version
+    VersionMismatch,
+    /// Kicks everyone with a reason, like a TShock server with a whitelist or a ban
+    Kick(String),
+    /// Kicks vanilla clients and sends tModLoader clients the mods they need
+    TModLoader {
+        /// Like `2023.8.3.4`
+        version: String,
+        /// Names and versions
+        mods: Vec<(String, String)>,
+    },
+}
+
+/// What the server sends and how it sends it
+#[derive(Clone, Debug)]
+pub struct MockConfig {
+    pub behavior: Behavior,
+    /// The protocol version clients need to get in
+    pub protocol: u32,
+    pub world_name: String,
+    /// The names of the players that are online, in slot 0, 1, ...
+    pub players: Vec<String>,
+    /// A chat message sent to players when they spawn
+    pub motd: Option<String>,
+    /// Splits every packet into writes of at most this many bytes
+    pub fragment_size: Option<usize>,
+    /// How long to wait before every write
+    pub delay: Duration,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            behavior: Behavior::Vanilla,
+            protocol: LATEST.protocol,
+            world_name: "Chlorophyte Mock".to_string(),
+            players: vec![],
+            motd: None,
+            fragment_size: None,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+pub struct MockServer {
+    listener: TcpListener,
+    config: Arc<MockConfig>,
+}
+
+impl MockServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A, config: MockConfig) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            config: Arc::new(config),
+        })
+    }
+
+    /// Starts a server on a free port of the loopback interface in the background
+    /// and returns its address
+    pub fn start(config: MockConfig) -> io::Result<SocketAddr> {
+        let server = Self::bind("127.0.0.1:0", config)?;
+        let addr = server.local_addr()?;
+        spawn(move || server.run());
+        Ok(addr)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts connections forever, handling each of them on its own thread
+    pub fn run(self) {
+        for stream in self.listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let config = self.config.clone();
+            spawn(move || {
+                let peer = stream.peer_addr();
+                let result = Connection::new(stream, &config).and_then(|mut c| c.handle());
+                match (peer, result) {
+                    (Ok(peer), Err(e)) => debug!("{peer}: {e}"),
+                    (Ok(peer), Ok(())) => debug!("{peer}: Disconnected"),
+                    _ => {}
+                }
+            });
+        }
+    }
+}
+
+struct Connection<'a> {
+    stream: TcpStream,
+    config: &'a MockConfig,
+    /// The slot the client gets, the one after the players that are online
+    slot: u8,
+    approved: bool,
+}
+
+impl<'a> Connection<'a> {
+    fn new(stream: TcpStream, config: &'a MockConfig) -> error::Result<Self> {
+        // Every write should become its own segment, so fragments arrive as fragments
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            config,
+            slot: u8::try_from(config.players.len()).unwrap_or(u8::MAX),
+            approved: false,
+        })
+    }
+
+    fn send<P: TerrariaPacket>(&mut self, packet: P) -> error::Result<()> {
+        let mut frame = vec![];
+        frame.write_terraria_packet(packet)?;
+        let fragment_size = self.config.fragment_size.unwrap_or(frame.len()).max(1);
+        for fragment in frame.chunks(fragment_size) {
+            sleep(self.config.delay);
+            self.stream.write_all(fragment)?;
+        }
+        Ok(())
+    }
+
+    fn kick(&mut self, reason: NetworkText) -> error::Result<()> {
+        self.send(S2CFatalError { error: reason })
+    }
+
+    /// Answers the packets of the client until it disconnects or gets kicked
+    fn handle(&mut self) -> error::Result<()> {
+        let mut decoder = TerrariaFrameDecoder::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = self.stream.read(&mut buf)?;
+            if n == 0 {
+                return Ok(());
+            }
+            decoder.feed(&buf[..n]);
+            while let Some((id, body)) = decoder.next_frame()? {
+                if !self.answer(C2SPacket::deserialize(id, body)?)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Answers one packet of the client, returns false if the client was kicked
+    fn answer(&mut self, packet: C2SPacket) -> error::Result<bool> {
+        match packet {
+            C2SPacket::Connect(connect) => return self.connect(connect.version),
+            C2SPacket::SendPassword(password) => {
+                let Behavior::PasswordProtected(expected) = &self.config.behavior else {
+                    return Ok(true);
+                };
+                if password.password != *expected {
+                    // "Incorrect password."
+                    self.kick(NetworkText::LocalizationKey(
+                        "LegacyMultiplayer.1".to_string(),
+                        vec![],
+                    ))?;
+                    return Ok(false);
+                }
+                self.approve()?;
+            }
+            C2SPacket::RequestWorldInfo(_) if self.approved => {
+                self.send(world::world_info(self.config))?;
+            }
+            C2SPacket::RequestInitialTileData(_) if self.approved => self.send_spawn_area()?,
+            C2SPacket::SpawnPlayer(_) if self.approved => {
+                if let Some(motd) = self.config.motd.clone() {
+                    let mut body = vec![SERVER_AUTHOR];
+                    body.write_network_text(NetworkText::Literal(motd))?;
+                    body.write_terraria_rgb((255, 240, 20))?;
+                    self.send(S2CNetModule { module_id: 1, body })?;
+                }
+                self.send(S2CFinishedConnectingToServer)?;
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn connect(&mut self, version: ClientVersion) -> error::Result<bool> {
+        let version_mismatch =
+            NetworkText::LocalizationKey("LegacyMultiplayer.4".to_string(), vec![]);
+        match (&self.config.behavior, version) {
+            (Behavior::VersionMismatch, _) => self.kick(version_mismatch)?,
+            (Behavior::Kick(reason), _) => self.kick(NetworkText::Literal(reason.clone()))?,
+            (Behavior::TModLoader { mods, .. }, ClientVersion::TModLoader(_)) => {
+                let mods = mods
+                    .iter()
+                    .map(|(name, version)| ModInfo {
+                        name: name.clone(),
+                        version: version.clone(),
+                        hash: [0; 20],
+                        signed: false,
+                        configs: vec![],
+                    })
+                    .collect();
+                self.send(S2CSyncMods {
+                    allow_vanilla_clients: false,
+                    mods,
+                })?;
+                return Ok(true);
+            }
+            (Behavior::TModLoader { version, .. }, _) => {
+                let reason = format!(
+                    "You are not using the same version as this server. This server is running tModLoader v{version}"
+                );
+                self.kick(NetworkText::Literal(reason))?;
+            }
+            (_, ClientVersion::Vanilla(protocol)) if protocol == self.config.protocol => {
+                if matches!(self.config.behavior, Behavior::PasswordProtected(_)) {
+                    self.send(S2CPasswordRequired)?;
+                } else {
+                    self.approve()?;
+                }
+                return Ok(true);
+            }
+            _ => self.kick(version_mismatch)?,
+        }
+        Ok(false)
+    }
+
+    fn approve(&mut self) -> error::Result<()> {
+        info!("{}: Approved", self.stream.peer_addr()?);
+        self.approved = true;
+        self.send(S2CConnectionApproved {
+            slot: self.slot,
+            check_bytes: false,
+        })
+    }
+
+    /// What a vanilla server sends after the client requested the tile data: the section
+    /// with the spawn in it, the players that are online and the go-ahead to spawn
+    fn send_spawn_area(&mut self) -> error::Result<()> {
+        self.send(S2CStatusText {
+            status_max: 1,
+            // "Receiving tile data"
+            text: NetworkText::LocalizationKey("LegacyInterface.44".to_string(), vec![]),
+            flags: 0,
+        })?;
+        self.send(world::spawn_section()?)?;
+        let section_x = SPAWN_X / world::SECTION_WIDTH;
+        let section_y = SPAWN_Y / world::SECTION_HEIGHT;
+        self.send(S2CTileFrameSection {
+            start_x: section_x,
+            start_y: section_y,
+            end_x: section_x,
+            end_y: section_y,
+        })?;
+        for (slot, name) in self.config.players.clone().into_iter().enumerate() {
+            let slot = u8::try_from(slot).unwrap_or(u8::MAX);
+            self.send(player_info(slot, name))?;
+            self.send(S2CPlayerActive { slot, active: true })?;
+        }
+        self.send(S2CCompleteConnectionAndSpawn)
+    }
+}
+
+fn player_info(slot: u8, name: String) -> S2CPlayerInfo {
+    S2CPlayerInfo {
+        slot,
+        skin_variant: 0,
+        hair: 0,
+        name,
+        hair_dye: 0,
+        hide_visible_accessory: [false; 10],
+        hide_misc: 0,
+        hair_color: (215, 90, 55),
+        skin_color: (255, 125, 90),
+        eye_color: (105, 90, 75),
+        shirt_color: (175, 165, 140),
+        undershirt_color: (160, 180, 215),
+        pants_color: (255, 230, 175),
+        shoe_color: (160, 105, 60),
+        difficulty: Difficulty::Normal,
+        extra_accessory: false,
+        torch_flags: 0,
+        upgrade_flags: 0,
+    }
+}
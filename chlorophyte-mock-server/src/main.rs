@@ -0,0 +1,68 @@
+#![warn(clippy::all, clippy::nursery, clippy::pedantic)]
+
+use chlorophyte_mock_server::{Behavior, MockConfig, MockServer};
+use log::{info, Level};
+use std::env::{args, var};
+use std::time::Duration;
+
+fn main() {
+    if var("RUST_LOG").is_err() {
+        simple_logger::init_with_level(Level::Info).unwrap();
+    } else {
+        simple_logger::init_with_env().unwrap();
+    }
+    let mut config = MockConfig::default();
+    let mut port = 7777u16;
+    let mut mods = vec![];
+    let mut tmodloader = None;
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("No value specified for {arg}"))
+        };
+        match arg.as_str() {
+            "--password" => config.behavior = Behavior::PasswordProtected(value()),
+            "--version-mismatch" => config.behavior = Behavior::VersionMismatch,
+            "--kick" => config.behavior = Behavior::Kick(value()),
+            "--tmodloader" => tmodloader = Some(value()),
+            "--mod" => {
+                let value = value();
+                let (name, version) = value
+                    .split_once(':')
+                    .expect("Mods have to be specified as <name>:<version>");
+                mods.push((name.to_string(), version.to_string()));
+            }
+            "--protocol" => {
+                config.protocol = value().parse().expect("Failed to parse protocol as u32");
+            }
+            "--world" => config.world_name = value(),
+            "--player" => config.players.push(value()),
+            "--motd" => config.motd = Some(value()),
+            "--fragment" => {
+                config.fragment_size = Some(
+                    value()
+                        .parse()
+                        .expect("Failed to parse fragment size as usize"),
+                );
+            }
+            "--delay" => {
+                let millis = value().parse().expect("Failed to parse delay as u64");
+                config.delay = Duration::from_millis(millis);
+            }
+            flag if flag.starts_with("--") => panic!("Unknown flag: {flag}"),
+            _ => port = arg.parse().expect("Failed to parse port as u16"),
+        }
+    }
+    if let Some(version) = tmodloader {
+        config.behavior = Behavior::TModLoader { version, mods };
+    }
+
+    let server = MockServer::bind(("0.0.0.0", port), config.clone()).expect("Failed to bind");
+    info!(
+        "Listening on {} as {:?}",
+        server.local_addr().unwrap(),
+        config.behavior
+    );
+    server.run();
+}
@@ -0,0 +1,122 @@
+//! The world the mock server pretends to run: a small classic world with flat ground
+
+use crate::MockConfig;
+use chlorophyte_terraria_protocol::error;
+use chlorophyte_terraria_protocol::packet::{S2CSendSection, S2CWorldInfo, WorldFlags};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+pub const MAX_TILES_X: i16 = 4200;
+pub const MAX_TILES_Y: i16 = 1200;
+pub const SPAWN_X: i16 = 2100;
+/// The first row of ground at the spawn
+pub const SPAWN_Y: i16 = 350;
+/// The size of the sections a vanilla server sends
+pub const SECTION_WIDTH: i16 = 200;
+pub const SECTION_HEIGHT: i16 = 150;
+
+const TILE_DIRT: u8 = 0;
+const TILE_GRASS: u8 = 2;
+
+pub fn world_info(config: &MockConfig) -> S2CWorldInfo {
+    S2CWorldInfo {
+        time: 13_500,
+        day_time: true,
+        blood_moon: false,
+        eclipse: false,
+        moon_phase: 0,
+        max_tiles_x: MAX_TILES_X,
+        max_tiles_y: MAX_TILES_Y,
+        spawn_x: SPAWN_X,
+        spawn_y: SPAWN_Y,
+        world_surface: 360,
+        rock_layer: 470,
+        world_id: 1_234_567,
+        world_name: config.world_name.clone(),
+        game_mode: 0,
+        unique_id: *b"chlorophyte-mock",
+        world_generator_version: 1_198_295_875_585,
+        moon_type: 1,
+        backgrounds: [0, 1, 2, 3, 1, 0, 2, 4, 1, 2, 0, 1, 0],
+        ice_back_style: 1,
+        jungle_back_style: 0,
+        hell_back_style: 2,
+        wind_speed_target: 0.1,
+        num_clouds: 40,
+        tree_x: [1000, 2500, 3300],
+        tree_style: [0, 1, 2, 3],
+        cave_back_x: [900, 2400, 3600],
+        cave_back_style: [0, 1, 2, 3],
+        tree_tops: [0; 13],
+        max_raining: 0.0,
+        flags: WorldFlags::default(),
+        sundial_cooldown: 0,
+        moondial_cooldown: 0,
+        ore_tiers: [7, 6, 9, 8, 107, 108, 111],
+        invasion_type: 0,
+        lobby_id: 0,
+        sandstorm_severity: 0.0,
+    }
+}
+
+/// The section with the spawn in it, compressed like a vanilla server does it.
+/// Everything above the spawn is air, then there's a row of grass and dirt below it
+pub fn spawn_section() -> error::Result<S2CSendSection> {
+    let x = i32::from(SPAWN_X / SECTION_WIDTH * SECTION_WIDTH);
+    let y = i32::from(SPAWN_Y / SECTION_HEIGHT * SECTION_HEIGHT);
+    let mut tiles = vec![];
+    for tile_y in y..y + i32::from(SECTION_HEIGHT) {
+        let tile = match tile_y - i32::from(SPAWN_Y) {
+            ..=-1 => None,
+            0 => Some(TILE_GRASS),
+            _ => Some(TILE_DIRT),
+        };
+        tiles.extend(std::iter::repeat_n(tile, SECTION_WIDTH as usize));
+    }
+
+    let mut section = vec![];
+    section.extend_from_slice(&x.to_le_bytes());
+    section.extend_from_slice(&y.to_le_bytes());
+    section.extend_from_slice(&SECTION_WIDTH.to_le_bytes());
+    section.extend_from_slice(&SECTION_HEIGHT.to_le_bytes());
+    let mut i = 0;
+    while i < tiles.len() {
+        let run = tiles[i..]
+            .iter()
+            .take(usize::from(u16::MAX) + 1)
+            .take_while(|t| **t == tiles[i])
+            .count();
+        write_tile(&mut section, tiles[i], run - 1);
+        i += run;
+    }
+    // No chests, signs or tile entities
+    section.extend_from_slice(&[0; 6]);
+
+    let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+    encoder.write_all(&section)?;
+    Ok(S2CSendSection {
+        compressed: true,
+        data: encoder.finish()?,
+    })
+}
+
+/// Writes a tile without wall, liquid, wires or paint, repeated `repeat` more times
+fn write_tile(section: &mut Vec<u8>, tile: Option<u8>, repeat: usize) {
+    let mut header1 = 0u8;
+    if tile.is_some() {
+        header1 |= 0b0000_0010;
+    }
+    match repeat {
+        0 => {}
+        1..=0xFF => header1 |= 0b0100_0000,
+        _ => header1 |= 0b1000_0000,
+    }
+    section.push(header1);
+    section.extend(tile);
+    match repeat {
+        0 => {}
+        1..=0xFF => section.push(repeat as u8),
+        _ => section.extend_from_slice(&(repeat as u16).to_le_bytes()),
+    }
+}
@@ -0,0 +1,233 @@
+use chlorophyte_mock_server::{Behavior, MockConfig, MockServer, SPAWN_X, SPAWN_Y};
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::packet::{
+    C2SConnect, C2SRequestInitialTileData, C2SRequestWorldInfo, C2SSendPassword, C2SSpawnPlayer,
+    S2CPacket, TerrariaPacket, WriteTerrariaPacket,
+};
+use chlorophyte_terraria_protocol::version::ClientVersion;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+struct TestClient {
+    stream: TcpStream,
+    decoder: TerrariaFrameDecoder,
+    /// How many reads it took to get all packets so far
+    reads: usize,
+}
+
+impl TestClient {
+    fn connect(addr: SocketAddr, version: ClientVersion) -> Self {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut client = Self {
+            stream,
+            decoder: TerrariaFrameDecoder::new(),
+            reads: 0,
+        };
+        client.send(C2SConnect { version });
+        client
+    }
+
+    fn send<P: TerrariaPacket>(&mut self, packet: P) {
+        let mut frame = vec![];
+        frame.write_terraria_packet(packet).unwrap();
+        self.stream.write_all(&frame).unwrap();
+    }
+
+    fn recv(&mut self) -> S2CPacket {
+        let mut buf = [0u8; 4096];
+        loop {
+            if let Some(packet) = self.decoder.next_packet().unwrap() {
+                return packet;
+            }
+            let n = self.stream.read(&mut buf).unwrap();
+            assert_ne!(n, 0, "The server disconnected");
+            self.reads += 1;
+            self.decoder.feed(&buf[..n]);
+        }
+    }
+
+    /// Walks the join sequence after the connection was approved
+    /// and returns everything the server sent
+    fn join(&mut self) -> Vec<S2CPacket> {
+        self.send(C2SRequestWorldInfo);
+        let mut packets = vec![self.recv()];
+        self.send(C2SRequestInitialTileData { x: -1, y: -1 });
+        loop {
+            let packet = self.recv();
+            let spawn = matches!(packet, S2CPacket::CompleteConnectionAndSpawn(_));
+            let finished = matches!(packet, S2CPacket::FinishedConnectingToServer(_));
+            packets.push(packet);
+            if spawn {
+                self.send(C2SSpawnPlayer {
+                    slot: 0,
+                    spawn_x: -1,
+                    spawn_y: -1,
+                    respawn_timer: 0,
+                    deaths_pve: 0,
+                    deaths_pvp: 0,
+                    context: 1,
+                });
+            }
+            if finished {
+                return packets;
+            }
+        }
+    }
+}
+
+fn kick_reason(packet: S2CPacket) -> String {
+    let S2CPacket::FatalError(error) = packet else {
+        panic!("Expected a kick, got {packet:?}");
+    };
+    error.error.render()
+}
+
+#[test]
+fn vanilla_join() {
+    let addr = MockServer::start(MockConfig {
+        world_name: "Loopback".to_string(),
+        players: vec!["Alice".to_string(), "Bob".to_string()],
+        motd: Some("Welcome!".to_string()),
+        ..MockConfig::default()
+    })
+    .unwrap();
+    let mut client = TestClient::connect(addr, ClientVersion::default());
+    let S2CPacket::ConnectionApproved(approved) = client.recv() else {
+        panic!("Expected the connection to be approved");
+    };
+    assert_eq!(approved.slot, 2);
+
+    let packets = client.join();
+    let S2CPacket::WorldInfo(world_info) = &packets[0] else {
+        panic!("Expected the world info");
+    };
+    assert_eq!(world_info.world_name, "Loopback");
+    let players: Vec<_> = packets
+        .iter()
+        .filter_map(|p| match p {
+            S2CPacket::PlayerInfo(info) => Some(info.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(players, ["Alice", "Bob"]);
+
+    let section = packets
+        .iter()
+        .find_map(|p| match p {
+            S2CPacket::SendSection(section) => Some(section),
+            _ => None,
+        })
+        .unwrap();
+    let tiles = section.tiles().unwrap();
+    let ground = tiles.get(SPAWN_X.into(), SPAWN_Y.into()).unwrap();
+    assert_eq!(ground.tile_type, Some(2));
+    let sky = tiles.get(SPAWN_X.into(), i32::from(SPAWN_Y) - 1).unwrap();
+    assert_eq!(sky.tile_type, None);
+
+    let motd = packets.iter().find_map(|p| match p {
+        S2CPacket::NetModule(module) => Some(module.decode().unwrap()),
+        _ => None,
+    });
+    assert!(format!("{motd:?}").contains("Welcome!"));
+}
+
+#[test]
+fn password() {
+    let addr = MockServer::start(MockConfig {
+        behavior: Behavior::PasswordProtected("hunter2".to_string()),
+        ..MockConfig::default()
+    })
+    .unwrap();
+
+    let mut client = TestClient::connect(addr, ClientVersion::default());
+    assert!(matches!(client.recv(), S2CPacket::PasswordRequired(_)));
+    client.send(C2SSendPassword {
+        password: "hunter2".to_string(),
+    });
+    assert!(matches!(client.recv(), S2CPacket::ConnectionApproved(_)));
+
+    let mut client = TestClient::connect(addr, ClientVersion::default());
+    assert!(matches!(client.recv(), S2CPacket::PasswordRequired(_)));
+    client.send(C2SSendPassword {
+        password: "wrong".to_string(),
+    });
+    assert_eq!(kick_reason(client.recv()), "Incorrect password.");
+}
+
+#[test]
+fn version_mismatch() {
+    let addr = MockServer::start(MockConfig::default()).unwrap();
+    let mut client = TestClient::connect(addr, ClientVersion::Vanilla(248));
+    let S2CPacket::FatalError(error) = client.recv() else {
+        panic!("Expected a kick");
+    };
+    assert!(error.is_version_mismatch());
+
+    let addr = MockServer::start(MockConfig {
+        behavior: Behavior::VersionMismatch,
+        ..MockConfig::default()
+    })
+    .unwrap();
+    let mut client = TestClient::connect(addr, ClientVersion::default());
+    let S2CPacket::FatalError(error) = client.recv() else {
+        panic!("Expected a kick");
+    };
+    assert!(error.is_version_mismatch());
+}
+
+#[test]
+fn kick() {
+    let addr = MockServer::start(MockConfig {
+        behavior: Behavior::Kick("You are not on the whitelist.".to_string()),
+        ..MockConfig::default()
+    })
+    .unwrap();
+    let mut client = TestClient::connect(addr, ClientVersion::default());
+    assert_eq!(kick_reason(client.recv()), "You are not on the whitelist.");
+}
+
+#[test]
+fn tmodloader() {
+    let addr = MockServer::start(MockConfig {
+        behavior: Behavior::TModLoader {
+            version: "2023.8.3.4".to_string(),
+            mods: vec![("CalamityMod".to_string(), "2.0.3.1".to_string())],
+        },
+        ..MockConfig::default()
+    })
+    .unwrap();
+
+    let mut client = TestClient::connect(addr, ClientVersion::default());
+    let S2CPacket::FatalError(error) = client.recv() else {
+        panic!("Expected a kick");
+    };
+    assert!(error.is_tmodloader());
+    assert_eq!(error.tmodloader_version().as_deref(), Some("2023.8.3.4"));
+
+    let mut client = TestClient::connect(addr, ClientVersion::TModLoader("2023.8.3.4".to_string()));
+    let S2CPacket::SyncMods(sync_mods) = client.recv() else {
+        panic!("Expected the mod list");
+    };
+    assert_eq!(sync_mods.mods[0].name, "CalamityMod");
+    assert_eq!(sync_mods.mods[0].version, "2.0.3.1");
+}
+
+#[test]
+fn fragmented() {
+    let addr = MockServer::start(MockConfig {
+        fragment_size: Some(3),
+        delay: Duration::from_millis(1),
+        ..MockConfig::default()
+    })
+    .unwrap();
+    let mut client = TestClient::connect(addr, ClientVersion::default());
+    assert!(matches!(client.recv(), S2CPacket::ConnectionApproved(_)));
+    client.send(C2SRequestWorldInfo);
+    assert!(matches!(client.recv(), S2CPacket::WorldInfo(_)));
+    // The world info alone is way bigger than a few fragments
+    assert!(client.reads > 10);
+}
@@ -0,0 +1,249 @@
+//! `#[derive(TerrariaPacket)]` for the packet structs of `chlorophyte-terraria-protocol`.
+//!
+//! The struct needs a `#[packet(id = 16, direction = ClientToServer)]` attribute. Fields are
+//! encoded in the order they're declared, based on their type through the `PacketField` trait:
+//! .NET integers and floats are little endian, `bool`s are a byte, `String`s have a 7-bit encoded
+//! length, `Rgb` is three bytes, `Vector2` two floats and arrays are their elements one after
+//! another. Fields can change how they're encoded with these attributes:
+//!
+//! - `#[packet(varint)]`: An `i32` sent as a 7-bit encoded int
+//! - `#[packet(bitmask)]`: A `[bool; N]` packed into as few bytes as fit it, least significant
+//!   bit first
+//! - `#[packet(bits = "group", bit = 3)]`: A `bool` stored in bit 3 of a byte shared with the
+//!   other fields of the group. The byte is sent where the first field of the group is declared
+//! - `#[packet(bits = "group")]`: A `PacketBits` type, like `Difficulty`, that takes up several
+//!   bits of the group byte
+//! - `#[packet(rest)]`: A `Vec<u8>` with the rest of the packet, for bodies decoded separately
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitInt, LitStr};
+
+#[proc_macro_derive(TerrariaPacket, attributes(packet))]
+pub fn derive_terraria_packet(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a single field is sent
+enum Encoding {
+    Field,
+    Varint,
+    Bitmask,
+    Rest,
+}
+
+/// A field that's sent on its own, or a group of fields that share one byte
+enum Item {
+    Field(Ident, Encoding),
+    Bits(String, Vec<(Ident, Option<u8>)>),
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (id, direction) = container_attributes(input)?;
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            input,
+            "TerrariaPacket can only be derived for structs",
+        ));
+    };
+
+    let mut items: Vec<Item> = vec![];
+    let mut names = vec![];
+    match &data.fields {
+        Fields::Named(fields) => {
+            for field in &fields.named {
+                let ident = field.ident.clone().expect("Named fields have a name");
+                names.push(ident.clone());
+                match field_attributes(field)? {
+                    FieldAttribute::Encoding(encoding) => items.push(Item::Field(ident, encoding)),
+                    FieldAttribute::Bits(group, bit) => {
+                        let existing = items.iter_mut().find_map(|item| match item {
+                            Item::Bits(g, members) if *g == group => Some(members),
+                            _ => None,
+                        });
+                        match existing {
+                            Some(members) => members.push((ident, bit)),
+                            None => items.push(Item::Bits(group, vec![(ident, bit)])),
+                        }
+                    }
+                }
+            }
+        }
+        Fields::Unit => {}
+        Fields::Unnamed(_) => {
+            return Err(Error::new_spanned(
+                &data.fields,
+                "TerrariaPacket needs named fields",
+            ))
+        }
+    }
+
+    let krate = quote!(::chlorophyte_terraria_protocol);
+    let field = quote!(#krate::field);
+    let mut writes = vec![];
+    let mut reads = vec![];
+    for (i, item) in items.iter().enumerate() {
+        match item {
+            Item::Field(ident, encoding) => {
+                let (write, read) = match encoding {
+                    Encoding::Field => (
+                        quote!(#field::PacketField::write(#ident, &mut packet)),
+                        quote!(#field::PacketField::read(&mut c)),
+                    ),
+                    Encoding::Varint => (
+                        quote!(#krate::types::TerrariaTypesW::write_7bit_encoded_int(&mut packet, #ident)),
+                        quote!(#krate::types::TerrariaTypesR::read_7bit_encoded_int(&mut c)),
+                    ),
+                    Encoding::Bitmask => (
+                        quote!(#field::write_bitmask(&mut packet, #ident)),
+                        quote!(#field::read_bitmask(&mut c)),
+                    ),
+                    Encoding::Rest => (
+                        quote!(#field::write_rest(&mut packet, &#ident)),
+                        quote!(#field::read_rest(&mut c)),
+                    ),
+                };
+                writes.push(quote!(#write?;));
+                reads.push(quote!(let #ident = #read?;));
+            }
+            Item::Bits(_, members) => {
+                let bits = format_ident!("bits{}", i);
+                let mut write = vec![];
+                let mut read = vec![];
+                for (ident, bit) in members {
+                    if let Some(bit) = bit {
+                        write.push(quote!(#bits |= u8::from(#ident) << #bit;));
+                        read.push(quote!(let #ident = #bits & (1 << #bit) != 0;));
+                    } else {
+                        write.push(quote!(#bits |= #field::PacketBits::to_bits(#ident);));
+                        read.push(quote!(let #ident = #field::PacketBits::from_bits(#bits);));
+                    }
+                }
+                writes.push(quote! {
+                    let mut #bits = 0u8;
+                    #(#write)*
+                    packet.push(#bits);
+                });
+                reads.push(quote! {
+                    let #bits = <u8 as #field::PacketField>::read(&mut c)?;
+                    #(#read)*
+                });
+            }
+        }
+    }
+
+    let (destructure, construct, bytes) = if matches!(data.fields, Fields::Unit) {
+        (quote!(), quote!(Self), quote!(_))
+    } else {
+        (
+            quote!(let Self { #(#names),* } = self;),
+            quote!(Self { #(#names),* }),
+            quote!(bytes),
+        )
+    };
+    let mutable = if items.is_empty() {
+        quote!()
+    } else {
+        quote!(mut)
+    };
+    let cursor = if items.is_empty() {
+        quote!()
+    } else {
+        quote!(let mut c = ::std::io::Cursor::new(bytes);)
+    };
+
+    Ok(quote! {
+        impl #krate::packet::TerrariaPacket for #name {
+            const PACKET_ID: u8 = #id;
+            const DIRECTION: #krate::packet::Direction = #krate::packet::Direction::#direction;
+
+            fn serialize(self) -> #krate::error::Result<::std::vec::Vec<u8>> {
+                #destructure
+                let #mutable packet = ::std::vec::Vec::new();
+                #(#writes)*
+                ::std::result::Result::Ok(packet)
+            }
+
+            fn deserialize(#bytes: ::std::vec::Vec<u8>) -> #krate::error::Result<::std::boxed::Box<Self>> {
+                #cursor
+                #(#reads)*
+                ::std::result::Result::Ok(::std::boxed::Box::new(#construct))
+            }
+        }
+    })
+}
+
+/// Reads the packet id and direction from `#[packet(id = 16, direction = ClientToServer)]`
+fn container_attributes(input: &DeriveInput) -> syn::Result<(LitInt, Ident)> {
+    let mut id = None;
+    let mut direction = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("packet")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                id = Some(meta.value()?.parse::<LitInt>()?);
+            } else if meta.path.is_ident("direction") {
+                let value = meta.value()?.parse::<Ident>()?;
+                if value != "ClientToServer" && value != "ServerToClient" {
+                    return Err(meta.error("Expected ClientToServer or ServerToClient"));
+                }
+                direction = Some(value);
+            } else {
+                return Err(meta.error("Unknown packet attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    match (id, direction) {
+        (Some(id), Some(direction)) => Ok((id, direction)),
+        _ => Err(Error::new(
+            Span::call_site(),
+            "Missing #[packet(id = ..., direction = ...)]",
+        )),
+    }
+}
+
+enum FieldAttribute {
+    Encoding(Encoding),
+    Bits(String, Option<u8>),
+}
+
+fn field_attributes(field: &syn::Field) -> syn::Result<FieldAttribute> {
+    let mut encoding = Encoding::Field;
+    let mut group = None;
+    let mut bit = None;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("packet")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("varint") {
+                encoding = Encoding::Varint;
+            } else if meta.path.is_ident("bitmask") {
+                encoding = Encoding::Bitmask;
+            } else if meta.path.is_ident("rest") {
+                encoding = Encoding::Rest;
+            } else if meta.path.is_ident("bits") {
+                group = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("bit") {
+                let value = meta.value()?.parse::<LitInt>()?.base10_parse::<u8>()?;
+                if value > 7 {
+                    return Err(meta.error("A byte only has bits 0 to 7"));
+                }
+                bit = Some(value);
+            } else {
+                return Err(meta.error("Unknown packet field attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    match (group, bit) {
+        (Some(group), bit) => Ok(FieldAttribute::Bits(group, bit)),
+        (None, Some(_)) => Err(Error::new_spanned(
+            field,
+            "`bit` needs a `bits` group to go with it",
+        )),
+        (None, None) => Ok(FieldAttribute::Encoding(encoding)),
+    }
+}
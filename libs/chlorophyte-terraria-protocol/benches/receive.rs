@@ -0,0 +1,75 @@
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::network_text::NetworkText;
+use chlorophyte_terraria_protocol::packet::{
+    ModInfo, S2CConnectionApproved, S2CFatalError, S2CNetModule, S2CSyncMods, WriteTerrariaPacket,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// What the receive thread of a scan sees: kicks, approvals, greetings and mod lists
+fn stream() -> Vec<u8> {
+    let mut stream = vec![];
+    stream
+        .write_terraria_packet(S2CFatalError {
+            error: NetworkText::LocalizationKey("LegacyMultiplayer.4".to_string(), vec![]),
+        })
+        .unwrap();
+    stream
+        .write_terraria_packet(S2CConnectionApproved {
+            slot: 3,
+            check_bytes: false,
+        })
+        .unwrap();
+    let mut chat = vec![1, 0, 255, 0];
+    chat.push(46);
+    chat.extend_from_slice(b"Welcome to the server! Type /help for commands");
+    chat.extend_from_slice(&[255, 240, 20]);
+    stream
+        .write_terraria_packet(S2CNetModule {
+            module_id: 1,
+            body: chat,
+        })
+        .unwrap();
+    stream
+        .write_terraria_packet(S2CSyncMods {
+            allow_vanilla_clients: false,
+            mods: ["CalamityMod", "MagicStorage", "RecipeBrowser"]
+                .into_iter()
+                .map(|name| ModInfo {
+                    name: name.to_string(),
+                    version: "2.0.3.1".to_string(),
+                    hash: [0xAB; 20],
+                    signed: true,
+                    configs: vec![(format!("{name}Config"), "{\"Enabled\": true}".to_string())],
+                })
+                .collect(),
+        })
+        .unwrap();
+    stream
+}
+
+fn receive(c: &mut Criterion) {
+    let stream = stream();
+    let mut group = c.benchmark_group("receive");
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            let mut decoder = TerrariaFrameDecoder::new();
+            decoder.feed(black_box(&stream));
+            while let Some(packet) = decoder.next_packet().unwrap() {
+                black_box(packet);
+            }
+        });
+    });
+    group.bench_function("view", |b| {
+        b.iter(|| {
+            let mut decoder = TerrariaFrameDecoder::new();
+            decoder.feed(black_box(&stream));
+            while let Some(packet) = decoder.next_view().unwrap() {
+                black_box(packet);
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, receive);
+criterion_main!(benches);
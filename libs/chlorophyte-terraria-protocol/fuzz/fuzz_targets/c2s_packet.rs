@@ -0,0 +1,11 @@
+#![no_main]
+
+use chlorophyte_terraria_protocol::packet::C2SPacket;
+use libfuzzer_sys::fuzz_target;
+
+// The packet id, then the body
+fuzz_target!(|data: &[u8]| {
+    if let Some((&id, body)) = data.split_first() {
+        let _ = C2SPacket::deserialize(id, body.to_vec());
+    }
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use libfuzzer_sys::fuzz_target;
+
+// A stream split into chunks the way TCP segments could split it, decoded both owned and borrowed.
+// A packet that fails to decode is skipped, so both loops end once the buffer runs dry
+fuzz_target!(|data: &[u8]| {
+    let mut owned = TerrariaFrameDecoder::new();
+    let mut view = TerrariaFrameDecoder::new();
+    for chunk in data.chunks(97) {
+        owned.feed(chunk);
+        while !matches!(owned.next_packet(), Ok(None)) {}
+        view.feed(chunk);
+        while !matches!(view.next_view(), Ok(None)) {}
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use chlorophyte_terraria_protocol::net_module::NetModule;
+use libfuzzer_sys::fuzz_target;
+
+// The module id, then the module body
+fuzz_target!(|data: &[u8]| {
+    if data.len() >= 2 {
+        let _ = NetModule::deserialize(u16::from_le_bytes([data[0], data[1]]), &data[2..]);
+    }
+});
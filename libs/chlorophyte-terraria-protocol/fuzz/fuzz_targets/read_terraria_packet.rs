@@ -0,0 +1,10 @@
+#![no_main]
+
+use chlorophyte_terraria_protocol::packet::ReadTerrariaPacket;
+use libfuzzer_sys::fuzz_target;
+use std::io::Cursor;
+
+// A whole frame as a server would send it, length prefix included
+fuzz_target!(|data: &[u8]| {
+    let _ = Cursor::new(data).read_any_packet();
+});
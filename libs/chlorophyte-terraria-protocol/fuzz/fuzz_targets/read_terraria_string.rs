@@ -0,0 +1,10 @@
+#![no_main]
+
+use chlorophyte_terraria_protocol::types::TerrariaTypesR;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = (&mut &data[..]).read_7bit_encoded_int();
+    let _ = (&mut &data[..]).read_terraria_string();
+    let _ = (&mut &data[..]).read_network_text();
+});
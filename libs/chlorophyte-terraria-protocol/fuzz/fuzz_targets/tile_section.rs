@@ -0,0 +1,11 @@
+#![no_main]
+
+use chlorophyte_terraria_protocol::packet::{S2CSendSection, TerrariaPacket};
+use libfuzzer_sys::fuzz_target;
+
+// The body of a send section packet, compressed or not
+fuzz_target!(|data: &[u8]| {
+    if let Ok(section) = S2CSendSection::deserialize(data.to_vec()) {
+        let _ = section.tiles();
+    }
+});
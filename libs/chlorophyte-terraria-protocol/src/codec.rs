@@ -0,0 +1,100 @@
+//! Async Terraria connections on top of tokio, enabled by the `tokio` feature
+
+use crate::error;
+use crate::error::ProtocolError;
+use crate::packet::{S2CPacket, TerrariaPacket, WriteTerrariaPacket};
+use bytes::{Buf, BufMut, BytesMut};
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio_util::codec::{Decoder, Encoder, Framed};
+
+/// Splits a stream into Terraria frames and writes packets with their length prefix and id.
+///
+/// Decoded frames are the packet id and body, like
+/// [`crate::frame::TerrariaFrameDecoder::next_frame`], so the codec works for both directions
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TerrariaCodec;
+
+impl Decoder for TerrariaCodec {
+    type Item = (u8, Vec<u8>);
+    type Error = ProtocolError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> error::Result<Option<Self::Item>> {
+        if src.len() < 2 {
+            return Ok(None);
+        }
+        let len = u16::from_le_bytes([src[0], src[1]]) as usize;
+        if len < 3 {
+            return Err(ProtocolError::LengthMismatch(
+                "Length prefix is smaller than the packet header",
+            ));
+        }
+        if src.len() < len {
+            src.reserve(len - src.len());
+            return Ok(None);
+        }
+
+        let mut frame = src.split_to(len);
+        frame.advance(2);
+        let id = frame.get_u8();
+        Ok(Some((id, frame.to_vec())))
+    }
+}
+
+impl<P: TerrariaPacket> Encoder<P> for TerrariaCodec {
+    type Error = ProtocolError;
+
+    fn encode(&mut self, packet: P, dst: &mut BytesMut) -> error::Result<()> {
+        dst.writer().write_terraria_packet(packet)
+    }
+}
+
+/// A connection to a Terraria server
+pub struct TerrariaConnection<T = TcpStream> {
+    framed: Framed<T, TerrariaCodec>,
+}
+
+impl TerrariaConnection<TcpStream> {
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> error::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream))
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> TerrariaConnection<T> {
+    pub fn new(stream: T) -> Self {
+        Self {
+            framed: Framed::new(stream, TerrariaCodec),
+        }
+    }
+
+    /// Writes a packet and flushes it
+    pub async fn send<P: TerrariaPacket>(&mut self, packet: P) -> error::Result<()> {
+        self.framed.send(packet).await
+    }
+
+    /// Waits for the next packet and returns its id and body.
+    /// Returns `Ok(None)` once the server closed the connection
+    pub async fn next_frame(&mut self) -> error::Result<Option<(u8, Vec<u8>)>> {
+        self.framed.next().await.transpose()
+    }
+
+    /// Waits for the next packet and decodes it.
+    /// Returns `Ok(None)` once the server closed the connection
+    pub async fn next_packet(&mut self) -> error::Result<Option<S2CPacket>> {
+        let Some((id, body)) = self.next_frame().await? else {
+            return Ok(None);
+        };
+        S2CPacket::deserialize(id, body).map(Some)
+    }
+
+    pub fn get_ref(&self) -> &T {
+        self.framed.get_ref()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.framed.into_inner()
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::ErrorKind;
+
+/// Why a packet couldn't be encoded or decoded
+#[derive(Debug)]
+pub enum ProtocolError {
+    /// The packet ended before everything in it was read
+    Truncated,
+    /// The packet has a different id than the one that was asked for
+    UnknownPacketId(u8),
+    /// A string has a negative length, isn't valid UTF-8 or isn't in the format it should be,
+    /// like an unknown client version
+    InvalidString,
+    /// A value isn't one of the values the game knows, like an unknown tile type
+    InvalidEnumValue { name: &'static str, value: i64 },
+    /// A length doesn't fit the data it describes, like a length prefix that's shorter than
+    /// the packet header
+    LengthMismatch(&'static str),
+    /// Something is larger than the protocol or this crate allows
+    TooLarge(&'static str),
+    /// Reading from or writing to the underlying stream failed
+    Io(io::Error),
+}
+
+impl ProtocolError {
+    pub(crate) fn invalid_enum_value(name: &'static str, value: impl Into<i64>) -> Self {
+        Self::InvalidEnumValue {
+            name,
+            value: value.into(),
+        }
+    }
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "Packet is truncated"),
+            Self::UnknownPacketId(id) => write!(f, "Unexpected packet id {id}"),
+            Self::InvalidString => write!(f, "Invalid string"),
+            Self::InvalidEnumValue { name, value } => write!(f, "Unknown {name} {value}"),
+            Self::LengthMismatch(what) => write!(f, "Length mismatch: {what}"),
+            Self::TooLarge(what) => write!(f, "Too large: {what}"),
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Running out of bytes while decoding means the packet was truncated
+impl From<io::Error> for ProtocolError {
+    fn from(value: io::Error) -> Self {
+        if value.kind() == ErrorKind::UnexpectedEof {
+            Self::Truncated
+        } else {
+            Self::Io(value)
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ProtocolError>;
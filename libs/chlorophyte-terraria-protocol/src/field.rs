@@ -0,0 +1,197 @@
+use crate::error;
+use crate::error::ProtocolError;
+use crate::network_text::NetworkText;
+use crate::packet::{Difficulty, WorldFlags};
+use crate::types::{TerrariaTypesR, TerrariaTypesW};
+use crate::version::ClientVersion;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// A value that can be a field of a packet, used by `#[derive(TerrariaPacket)]`
+pub trait PacketField: Sized {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()>;
+    fn read<R: Read>(r: &mut R) -> error::Result<Self>;
+}
+
+/// A value that takes up some of the bits of a byte it shares with other fields
+pub trait PacketBits {
+    fn to_bits(self) -> u8;
+    fn from_bits(bits: u8) -> Self;
+}
+
+macro_rules! little_endian {
+    ($($t:ty => $write:ident, $read:ident;)*) => {
+        $(
+            impl PacketField for $t {
+                fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+                    Ok(w.$write::<LittleEndian>(self)?)
+                }
+
+                fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+                    Ok(r.$read::<LittleEndian>()?)
+                }
+            }
+        )*
+    };
+}
+
+little_endian! {
+    u16 => write_u16, read_u16;
+    i16 => write_i16, read_i16;
+    u32 => write_u32, read_u32;
+    i32 => write_i32, read_i32;
+    u64 => write_u64, read_u64;
+    i64 => write_i64, read_i64;
+    f32 => write_f32, read_f32;
+    f64 => write_f64, read_f64;
+}
+
+impl PacketField for u8 {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        Ok(w.write_u8(self)?)
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        Ok(r.read_u8()?)
+    }
+}
+
+impl PacketField for i8 {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        Ok(w.write_i8(self)?)
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        Ok(r.read_i8()?)
+    }
+}
+
+/// Any byte other than 0 is true, like .NET's `BinaryReader.ReadBoolean`
+impl PacketField for bool {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        Ok(w.write_u8(u8::from(self))?)
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        Ok(r.read_u8()? != 0)
+    }
+}
+
+impl PacketField for String {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        w.write_terraria_string(self)
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        r.read_terraria_string()
+    }
+}
+
+impl PacketField for NetworkText {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        w.write_network_text(self)
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        r.read_network_text()
+    }
+}
+
+/// [`crate::types::Rgb`]
+impl PacketField for (u8, u8, u8) {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        w.write_terraria_rgb(self)
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        r.read_terraria_rgb()
+    }
+}
+
+/// [`crate::types::Vector2`]
+impl PacketField for (f32, f32) {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        w.write_terraria_vector2(self)
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        r.read_terraria_vector2()
+    }
+}
+
+impl<T: PacketField + Copy + Default, const N: usize> PacketField for [T; N] {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        for value in self {
+            value.write(w)?;
+        }
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        let mut values = [T::default(); N];
+        for value in &mut values {
+            *value = T::read(r)?;
+        }
+        Ok(values)
+    }
+}
+
+impl PacketField for WorldFlags {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        Ok(w.write_all(&self.to_bytes())?)
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        let mut bytes = [0u8; 10];
+        r.read_exact(&mut bytes)?;
+        Ok(Self::from_bytes(bytes))
+    }
+}
+
+impl PacketField for ClientVersion {
+    fn write<W: Write>(self, w: &mut W) -> error::Result<()> {
+        w.write_terraria_string(self.to_string())
+    }
+
+    fn read<R: Read>(r: &mut R) -> error::Result<Self> {
+        r.read_terraria_string()?
+            .parse()
+            .map_err(|()| ProtocolError::InvalidString)
+    }
+}
+
+impl PacketBits for Difficulty {
+    fn to_bits(self) -> u8 {
+        Self::to_bits(self)
+    }
+
+    fn from_bits(bits: u8) -> Self {
+        Self::from_bits(bits)
+    }
+}
+
+/// Writes the bools as bits, least significant bit of the first byte first
+pub fn write_bitmask<W: Write, const N: usize>(w: &mut W, bits: [bool; N]) -> error::Result<()> {
+    let mut bytes = vec![0u8; N.div_ceil(8)];
+    for (i, bit) in bits.into_iter().enumerate() {
+        bytes[i / 8] |= u8::from(bit) << (i % 8);
+    }
+    Ok(w.write_all(&bytes)?)
+}
+
+pub fn read_bitmask<R: Read, const N: usize>(r: &mut R) -> error::Result<[bool; N]> {
+    let mut bytes = vec![0u8; N.div_ceil(8)];
+    r.read_exact(&mut bytes)?;
+    Ok(std::array::from_fn(|i| bytes[i / 8] & (1 << (i % 8)) != 0))
+}
+
+pub fn write_rest<W: Write>(w: &mut W, rest: &[u8]) -> error::Result<()> {
+    Ok(w.write_all(rest)?)
+}
+
+/// Reads everything that's left of the packet
+pub fn read_rest<R: Read>(r: &mut R) -> error::Result<Vec<u8>> {
+    let mut rest = vec![];
+    r.read_to_end(&mut rest)?;
+    Ok(rest)
+}
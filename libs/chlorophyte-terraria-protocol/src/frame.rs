@@ -0,0 +1,86 @@
+use crate::error;
+use crate::error::ProtocolError;
+use crate::packet::S2CPacket;
+use crate::view::S2CPacketView;
+
+/// Reassembles Terraria packets from a TCP byte stream.
+///
+/// Feed it the payloads of the segments in order, no matter how the packets are split or
+/// coalesced, and take the complete packets out with [`Self::next_frame`] or [`Self::next_packet`].
+/// [`Self::next_frame_ref`] and [`Self::next_view`] do the same without copying the body.
+#[derive(Clone, Debug, Default)]
+pub struct TerrariaFrameDecoder {
+    buf: Vec<u8>,
+    /// Where the first packet that wasn't taken out yet starts
+    start: usize,
+}
+
+impl TerrariaFrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a chunk of the stream to the buffer
+    pub fn feed(&mut self, bytes: &[u8]) {
+        // Packets that were taken out are only dropped here, so their bodies can be borrowed
+        // until more bytes arrive
+        if self.start > 0 {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Number of bytes that were fed, but are not part of a complete packet yet
+    pub fn buffered(&self) -> usize {
+        self.buf.len() - self.start
+    }
+
+    /// Takes the next complete packet out of the buffer and returns its id and body,
+    /// borrowed from the buffer.
+    ///
+    /// Returns `Ok(None)` if more bytes are needed. An invalid length prefix means the stream
+    /// can't be resynchronised, so the buffer is discarded and an error is returned.
+    pub fn next_frame_ref(&mut self) -> error::Result<Option<(u8, &[u8])>> {
+        let buf = &self.buf[self.start..];
+        if buf.len() < 2 {
+            return Ok(None);
+        }
+        let len = u16::from_le_bytes([buf[0], buf[1]]) as usize;
+        if len < 3 {
+            self.buf.clear();
+            self.start = 0;
+            return Err(ProtocolError::LengthMismatch(
+                "Length prefix is smaller than the packet header",
+            ));
+        }
+        if buf.len() < len {
+            return Ok(None);
+        }
+
+        let frame = &self.buf[self.start..self.start + len];
+        self.start += len;
+        Ok(Some((frame[2], &frame[3..])))
+    }
+
+    /// Takes the next complete packet out of the buffer and returns its id and body
+    pub fn next_frame(&mut self) -> error::Result<Option<(u8, Vec<u8>)>> {
+        Ok(self.next_frame_ref()?.map(|(id, body)| (id, body.to_vec())))
+    }
+
+    /// Takes the next complete packet out of the buffer and decodes it
+    pub fn next_packet(&mut self) -> error::Result<Option<S2CPacket>> {
+        let Some((id, body)) = self.next_frame()? else {
+            return Ok(None);
+        };
+        S2CPacket::deserialize(id, body).map(Some)
+    }
+
+    /// Takes the next complete packet out of the buffer and parses it without copying
+    pub fn next_view(&mut self) -> error::Result<Option<S2CPacketView<'_>>> {
+        let Some((id, body)) = self.next_frame_ref()? else {
+            return Ok(None);
+        };
+        S2CPacketView::parse(id, body).map(Some)
+    }
+}
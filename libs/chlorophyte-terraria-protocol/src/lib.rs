@@ -1,2 +1,15 @@
+// Lets the code `#[derive(TerrariaPacket)]` generates refer to this crate by name
+extern crate self as chlorophyte_terraria_protocol;
+
+#[cfg(feature = "tokio")]
+pub mod codec;
+pub mod error;
+pub mod field;
+pub mod frame;
+pub mod net_module;
+pub mod network_text;
 pub mod packet;
+pub mod tile;
 pub mod types;
+pub mod version;
+pub mod view;
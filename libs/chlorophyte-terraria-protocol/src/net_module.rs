@@ -0,0 +1,170 @@
+use crate::error;
+use crate::error::ProtocolError;
+use crate::network_text::NetworkText;
+use crate::types::{Rgb, TerrariaTypesR, Vector2};
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::io::Cursor;
+
+/// The author of text module messages that were sent by the server itself
+pub const SERVER_AUTHOR: u8 = 255;
+
+/// A liquid that changed, as sent by the liquid module
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiquidChange {
+    pub x: u16,
+    pub y: u16,
+    pub amount: u8,
+    pub liquid_type: u8,
+}
+
+/// How a Bestiary entry was unlocked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BestiaryUnlock {
+    Kill { npc_id: i16, kill_count: u16 },
+    Sight { npc_id: i16 },
+    Chat { npc_id: i16 },
+}
+
+/// The contents of packet 82, decoded based on the module id.
+/// Module ids are assigned in the order the game registers the modules
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetModule {
+    /// Module 0
+    Liquid(Vec<LiquidChange>),
+    /// Module 1: Chat messages, including MOTDs and welcome messages
+    Text {
+        /// The player slot of the author, [`SERVER_AUTHOR`] if the server sent it
+        author: u8,
+        text: NetworkText,
+        color: Rgb,
+    },
+    /// Module 2: A ping on the map
+    Ping(Vector2),
+    /// Module 3
+    Ambience {
+        slot: u8,
+        seed: i32,
+        sky_entity_type: u8,
+    },
+    /// Module 4
+    Bestiary(BestiaryUnlock),
+    /// Module 5: How many of an item were researched in journey mode
+    CreativeUnlocks {
+        item_id: i16,
+        count: u16,
+    },
+    /// Module 6: A journey mode power was used. The body depends on the power
+    CreativePowers {
+        power_id: u16,
+        body: Vec<u8>,
+    },
+    /// Module 8
+    TeleportPylon {
+        action: u8,
+        x: i16,
+        y: i16,
+        pylon_type: u8,
+    },
+    /// Module 9
+    Particles {
+        particle_type: u8,
+        position: Vector2,
+        velocity: Vector2,
+        unique_info: i32,
+        slot: u8,
+    },
+    /// Module 10: Who is allowed to use a journey mode power
+    CreativePowerPermissions {
+        power_id: u16,
+        level: u8,
+    },
+    Unknown {
+        module_id: u16,
+        body: Vec<u8>,
+    },
+}
+
+impl NetModule {
+    /// Decodes a module body based on the module id
+    pub fn deserialize(module_id: u16, body: &[u8]) -> error::Result<Self> {
+        let mut c = Cursor::new(body);
+        Ok(match module_id {
+            0 => {
+                let count = c.read_u16::<LittleEndian>()?;
+                let mut changes = Vec::with_capacity(usize::from(count).min(body.len() / 6));
+                for _ in 0..count {
+                    let position = c.read_u32::<LittleEndian>()?;
+                    changes.push(LiquidChange {
+                        x: (position >> 16) as u16,
+                        y: position as u16,
+                        amount: c.read_u8()?,
+                        liquid_type: c.read_u8()?,
+                    });
+                }
+                Self::Liquid(changes)
+            }
+            1 => Self::Text {
+                author: c.read_u8()?,
+                text: c.read_network_text()?,
+                color: c.read_terraria_rgb()?,
+            },
+            2 => Self::Ping(c.read_terraria_vector2()?),
+            3 => Self::Ambience {
+                slot: c.read_u8()?,
+                seed: c.read_i32::<LittleEndian>()?,
+                sky_entity_type: c.read_u8()?,
+            },
+            4 => {
+                let unlock_type = c.read_u8()?;
+                let npc_id = c.read_i16::<LittleEndian>()?;
+                Self::Bestiary(match unlock_type {
+                    0 => BestiaryUnlock::Kill {
+                        npc_id,
+                        kill_count: c.read_u16::<LittleEndian>()?,
+                    },
+                    1 => BestiaryUnlock::Sight { npc_id },
+                    2 => BestiaryUnlock::Chat { npc_id },
+                    unlock_type => {
+                        return Err(ProtocolError::invalid_enum_value(
+                            "Bestiary unlock type",
+                            unlock_type,
+                        ))
+                    }
+                })
+            }
+            5 => Self::CreativeUnlocks {
+                item_id: c.read_i16::<LittleEndian>()?,
+                count: c.read_u16::<LittleEndian>()?,
+            },
+            6 => Self::CreativePowers {
+                power_id: c.read_u16::<LittleEndian>()?,
+                body: body[2..].to_vec(),
+            },
+            8 => Self::TeleportPylon {
+                action: c.read_u8()?,
+                x: c.read_i16::<LittleEndian>()?,
+                y: c.read_i16::<LittleEndian>()?,
+                pylon_type: c.read_u8()?,
+            },
+            9 => Self::Particles {
+                particle_type: c.read_u8()?,
+                position: c.read_terraria_vector2()?,
+                velocity: c.read_terraria_vector2()?,
+                unique_info: c.read_i32::<LittleEndian>()?,
+                slot: c.read_u8()?,
+            },
+            10 => {
+                // Always 0, the game only has one kind of permission
+                c.read_u8()?;
+                Self::CreativePowerPermissions {
+                    power_id: c.read_u16::<LittleEndian>()?,
+                    level: c.read_u8()?,
+                }
+            }
+            module_id => Self::Unknown {
+                module_id,
+                body: body.to_vec(),
+            },
+        })
+    }
+}
@@ -0,0 +1,175 @@
+use crate::types::MAX_STRING_LEN;
+use std::fmt::{Display, Formatter};
+
+/// English texts for common vanilla localization keys
+const TEXTS: &str = include_str!("../assets/en-US.txt");
+
+/// How deep substitutions can be nested before we refuse to decode them
+pub const MAX_DEPTH: usize = 8;
+
+/// How many texts one render goes through before it stops. Texts that render to nothing can
+/// still be substituted into each other thousands of times
+pub const MAX_RENDERED_TEXTS: usize = 4096;
+
+/// Text that the server lets the client localize, used for kick reasons, status texts and chat
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkText {
+    /// Mode 0: Text that is shown as-is
+    Literal(String),
+    /// Mode 1: A format string like `{0} has joined.` and the texts to put into it
+    Formattable(String, Vec<NetworkText>),
+    /// Mode 2: A localization key like `LegacyMultiplayer.4` and the texts to put into it
+    LocalizationKey(String, Vec<NetworkText>),
+}
+
+impl NetworkText {
+    pub const fn mode(&self) -> u8 {
+        match self {
+            Self::Literal(_) => 0,
+            Self::Formattable(..) => 1,
+            Self::LocalizationKey(..) => 2,
+        }
+    }
+
+    /// The localization key, if this is a [`NetworkText::LocalizationKey`]
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Self::LocalizationKey(key, _) => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Turns the text into English. Unknown localization keys are rendered as the key itself.
+    /// Rendering stops after [`MAX_RENDERED_TEXTS`] texts or [`MAX_STRING_LEN`] bytes, as
+    /// nested substitutions could make the text far larger than the packet it came in
+    pub fn render(&self) -> String {
+        render(self)
+    }
+
+    /// Whether a literal text, format or key in the text contains `needle`, without rendering it
+    pub fn mentions(&self, needle: &str) -> bool {
+        mentions(self, needle)
+    }
+}
+
+impl TextParts for NetworkText {
+    fn parts(&self) -> Parts<'_, Self> {
+        match self {
+            Self::Literal(text) => Parts::Literal(text),
+            Self::Formattable(format, substitutions) => Parts::Formattable(format, substitutions),
+            Self::LocalizationKey(key, substitutions) => Parts::LocalizationKey(key, substitutions),
+        }
+    }
+}
+
+impl Display for NetworkText {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+impl From<String> for NetworkText {
+    fn from(value: String) -> Self {
+        Self::Literal(value)
+    }
+}
+
+/// Looks up the English text of a localization key in the bundled key table
+pub fn lookup(key: &str) -> Option<&'static str> {
+    TEXTS
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+/// What rendering looks at, so [`NetworkText`] and its view render the same way
+pub(crate) enum Parts<'t, T> {
+    Literal(&'t str),
+    Formattable(&'t str, &'t [T]),
+    LocalizationKey(&'t str, &'t [T]),
+}
+
+pub(crate) trait TextParts: Sized {
+    fn parts(&self) -> Parts<'_, Self>;
+}
+
+pub(crate) fn render<T: TextParts>(text: &T) -> String {
+    let mut renderer = Renderer {
+        out: String::new(),
+        texts_left: MAX_RENDERED_TEXTS,
+    };
+    renderer.render(text);
+    renderer.out
+}
+
+pub(crate) fn mentions<T: TextParts>(text: &T, needle: &str) -> bool {
+    match text.parts() {
+        Parts::Literal(text) => text.contains(needle),
+        Parts::Formattable(text, substitutions) | Parts::LocalizationKey(text, substitutions) => {
+            text.contains(needle) || substitutions.iter().any(|s| mentions(s, needle))
+        }
+    }
+}
+
+struct Renderer {
+    out: String,
+    texts_left: usize,
+}
+
+impl Renderer {
+    fn is_done(&self) -> bool {
+        self.texts_left == 0 || self.out.len() >= MAX_STRING_LEN
+    }
+
+    fn render<T: TextParts>(&mut self, text: &T) {
+        if self.is_done() {
+            return;
+        }
+        self.texts_left -= 1;
+        match text.parts() {
+            Parts::Literal(text) => self.push(text),
+            Parts::Formattable(format, substitutions) => self.substitute(format, substitutions),
+            Parts::LocalizationKey(key, substitutions) => {
+                self.substitute(lookup(key).unwrap_or(key), substitutions);
+            }
+        }
+    }
+
+    /// Replaces `{0}`, `{1}`, ... with the rendered substitutions in one pass, so text that was
+    /// put in is never substituted again
+    fn substitute<T: TextParts>(&mut self, format: &str, substitutions: &[T]) {
+        let mut rest = format;
+        while let Some(start) = rest.find('{') {
+            if self.is_done() {
+                return;
+            }
+            self.push(&rest[..start]);
+            rest = &rest[start + 1..];
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let substitution = Some(&rest[digits..])
+                .filter(|after| digits > 0 && after.starts_with('}'))
+                .and_then(|_| substitutions.get(rest[..digits].parse::<usize>().ok()?));
+            match substitution {
+                Some(substitution) => {
+                    self.render(substitution);
+                    rest = &rest[digits + 1..];
+                }
+                None => self.push("{"),
+            }
+        }
+        self.push(rest);
+    }
+
+    /// Appends as much of `text` as fits into [`MAX_STRING_LEN`] bytes
+    fn push(&mut self, text: &str) {
+        let mut end = text
+            .len()
+            .min(MAX_STRING_LEN.saturating_sub(self.out.len()));
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.out.push_str(&text[..end]);
+    }
+}
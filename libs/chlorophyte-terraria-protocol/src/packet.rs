@@ -1,24 +1,34 @@
-use crate::types::{TerrariaTypesR, TerrariaTypesW};
+use crate::error;
+use crate::error::ProtocolError;
+use crate::net_module::NetModule;
+use crate::network_text::NetworkText;
+use crate::tile::{Bounds, TileGrid};
+use crate::types::{TerrariaTypesR, TerrariaTypesW, Vector2};
+use crate::version::ClientVersion;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+pub use chlorophyte_terraria_protocol_derive::TerrariaPacket;
+use flate2::read::DeflateDecoder;
 use std::fmt::Debug;
-use std::io::{Cursor, ErrorKind, Read, Write};
-use std::{io, vec};
+use std::io::{Cursor, Read, Write};
 
 pub type Rgb = (u8, u8, u8);
 
 pub trait ReadTerrariaPacket {
-    fn read_terraria_packet<P: S2CTerrariaPacket>(&mut self) -> io::Result<P>;
+    fn read_terraria_packet<P: TerrariaPacket>(&mut self) -> error::Result<P>;
+    fn read_any_packet(&mut self) -> error::Result<S2CPacket>;
 }
 pub trait WriteTerrariaPacket {
-    fn write_terraria_packet<P: C2STerrariaPacket>(&mut self, packet: P) -> io::Result<()>;
+    fn write_terraria_packet<P: TerrariaPacket>(&mut self, packet: P) -> error::Result<()>;
 }
 
 impl<T: Write> WriteTerrariaPacket for T {
     /// Writes a Terraria packet with length prefix, packet id and fields
-    fn write_terraria_packet<P: C2STerrariaPacket>(&mut self, packet: P) -> io::Result<()> {
-        let body = packet.serialize();
+    fn write_terraria_packet<P: TerrariaPacket>(&mut self, packet: P) -> error::Result<()> {
+        let body = packet.serialize()?;
+        let Ok(len) = u16::try_from(2 + 1 + body.len()) else {
+            return Err(ProtocolError::TooLarge("Packet"));
+        };
         let mut packet = vec![];
-        let len = 2 + 1 + body.len() as u16;
         packet.extend_from_slice(&len.to_le_bytes());
         packet.push(P::PACKET_ID);
         packet.extend_from_slice(&body);
@@ -29,91 +39,764 @@ impl<T: Write> WriteTerrariaPacket for T {
 }
 
 impl<T: Read + Debug> ReadTerrariaPacket for T {
-    /// Reads a Terraria packet with length prefix, packet id and fields
-    fn read_terraria_packet<P: S2CTerrariaPacket>(&mut self) -> io::Result<P> {
-        let len = self.read_u16::<LittleEndian>()?;
-        let mut buf = vec![0u8; len as usize];
-        let n = self.read(&mut buf)?;
-        if n < len as usize - 2 {
-            return Err(io::Error::new(ErrorKind::WouldBlock, "Packet is too short"));
+    /// Reads a Terraria packet with length prefix, packet id and fields.
+    /// Fails with [`ProtocolError::UnknownPacketId`] if the next packet is a different one
+    fn read_terraria_packet<P: TerrariaPacket>(&mut self) -> error::Result<P> {
+        let (id, body) = read_frame(self)?;
+        if id != P::PACKET_ID {
+            return Err(ProtocolError::UnknownPacketId(id));
         }
+        P::deserialize(body).map(|p| *p)
+    }
 
-        P::deserialize(buf[1..].to_vec()).map(|p| *p)
+    /// Reads a Terraria packet of any type and decodes it based on its packet id
+    fn read_any_packet(&mut self) -> error::Result<S2CPacket> {
+        let (id, body) = read_frame(self)?;
+        S2CPacket::deserialize(id, body)
     }
 }
 
-pub trait C2STerrariaPacket {
-    const PACKET_ID: u8;
-    fn serialize(self) -> Vec<u8>;
+/// Reads the length prefix and packet id, returning the id and the body
+fn read_frame<T: Read>(r: &mut T) -> error::Result<(u8, Vec<u8>)> {
+    let len = r.read_u16::<LittleEndian>()?;
+    if len < 3 {
+        return Err(ProtocolError::LengthMismatch(
+            "Length prefix is smaller than the packet header",
+        ));
+    }
+    let mut buf = vec![0u8; len as usize - 2];
+    r.read_exact(&mut buf)?;
+
+    let body = buf.split_off(1);
+    Ok((buf[0], body))
 }
 
-pub trait S2CTerrariaPacket {
-    fn deserialize(bytes: Vec<u8>) -> io::Result<Box<Self>>;
+/// Which way a packet is sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+/// A packet that can be encoded and decoded, no matter which side sends it
+pub trait TerrariaPacket {
+    const PACKET_ID: u8;
+    const DIRECTION: Direction;
+    /// Encodes the packet body, without the length prefix and packet id
+    fn serialize(self) -> error::Result<Vec<u8>>;
+    /// Decodes a packet body, without the length prefix and packet id
+    fn deserialize(bytes: Vec<u8>) -> error::Result<Box<Self>>;
 }
 
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 1, direction = ClientToServer)]
 pub struct C2SConnect {
-    pub version: u32,
+    pub version: ClientVersion,
 }
 
-impl C2STerrariaPacket for C2SConnect {
-    const PACKET_ID: u8 = 1;
-    fn serialize(self) -> Vec<u8> {
-        let mut packet: Vec<u8> = vec![];
-        packet
-            .write_terraria_string(format!("Terraria{}", self.version))
-            .unwrap();
-        packet
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 3, direction = ServerToClient)]
+pub struct S2CConnectionApproved {
+    pub slot: u8,
+    /// Whether the server wants the client to run its check bytes loop, always false
+    pub check_bytes: bool,
+}
+
+/// The longest tModLoader version [`find_tmodloader_version`] accepts
+pub const MAX_TMODLOADER_VERSION_LEN: usize = 32;
+
+/// The tModLoader version mentioned in a kick reason,
+/// e.g. `2023.8.3.4` for "... tModLoader v2023.8.3.4 ..."
+///
+/// Only up to four numbers of at most [`MAX_TMODLOADER_VERSION_LEN`] bytes are accepted,
+/// as the version is sent back to the server in a connection request
+pub fn find_tmodloader_version(text: &str) -> Option<String> {
+    let (_, rest) = text.split_once("tModLoader v")?;
+    let version = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .take(MAX_TMODLOADER_VERSION_LEN + 1)
+        .collect::<String>();
+    let version = version.trim_end_matches('.');
+    let valid = version.len() <= MAX_TMODLOADER_VERSION_LEN
+        && version.split('.').count() <= 4
+        && version.split('.').all(|part| !part.is_empty());
+    valid.then(|| version.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 2, direction = ServerToClient)]
+pub struct S2CFatalError {
+    pub error: NetworkText,
+}
+
+impl S2CFatalError {
+    /// Whether the server kicked us because we sent a different protocol version
+    pub fn is_version_mismatch(&self) -> bool {
+        self.error.key() == Some("LegacyMultiplayer.4")
+    }
+
+    /// Whether the kick reason comes from a tModLoader server, without rendering it
+    pub fn is_tmodloader(&self) -> bool {
+        self.error
+            .key()
+            .is_some_and(|k| k.starts_with("tModLoader."))
+            || self.error.mentions("tModLoader")
+    }
+
+    /// See [`find_tmodloader_version`]
+    pub fn tmodloader_version(&self) -> Option<String> {
+        find_tmodloader_version(&self.error.render())
     }
 }
 
-pub struct S2CConnectionApproved {
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 37, direction = ServerToClient)]
+pub struct S2CPasswordRequired;
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 4, direction = ServerToClient)]
+pub struct S2CPlayerInfo {
     pub slot: u8,
+    pub skin_variant: u8,
+    pub hair: u8,
+    pub name: String,
+    pub hair_dye: u8,
+    #[packet(bitmask)]
+    pub hide_visible_accessory: [bool; 10],
+    pub hide_misc: u8,
+    pub hair_color: Rgb,
+    pub skin_color: Rgb,
+    pub eye_color: Rgb,
+    pub shirt_color: Rgb,
+    pub undershirt_color: Rgb,
+    pub pants_color: Rgb,
+    pub shoe_color: Rgb,
+    #[packet(bits = "difficulty")]
+    pub difficulty: Difficulty,
+    #[packet(bits = "difficulty", bit = 2)]
+    pub extra_accessory: bool,
+    /// Biome torch and super cart bits
+    pub torch_flags: u8,
+    /// Consumed permanent upgrade bits
+    pub upgrade_flags: u8,
 }
 
-impl S2CTerrariaPacket for S2CConnectionApproved {
-    fn deserialize(bytes: Vec<u8>) -> io::Result<Box<Self>> {
-        let mut c = Cursor::new(bytes);
-        let slot = c.read_u8()?;
-        let _ = c.read_u8();
-        Ok(Box::new(Self { slot }))
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 7, direction = ServerToClient)]
+pub struct S2CWorldInfo {
+    pub time: i32,
+    #[packet(bits = "time", bit = 0)]
+    pub day_time: bool,
+    #[packet(bits = "time", bit = 1)]
+    pub blood_moon: bool,
+    #[packet(bits = "time", bit = 2)]
+    pub eclipse: bool,
+    pub moon_phase: u8,
+    pub max_tiles_x: i16,
+    pub max_tiles_y: i16,
+    pub spawn_x: i16,
+    pub spawn_y: i16,
+    pub world_surface: i16,
+    pub rock_layer: i16,
+    pub world_id: i32,
+    pub world_name: String,
+    pub game_mode: u8,
+    pub unique_id: [u8; 16],
+    pub world_generator_version: u64,
+    pub moon_type: u8,
+    /// Tree 1-4, corruption, jungle, snow, hallow, crimson, desert, ocean, mushroom and
+    /// underworld background styles
+    pub backgrounds: [u8; 13],
+    pub ice_back_style: u8,
+    pub jungle_back_style: u8,
+    pub hell_back_style: u8,
+    pub wind_speed_target: f32,
+    pub num_clouds: u8,
+    pub tree_x: [i32; 3],
+    pub tree_style: [u8; 4],
+    pub cave_back_x: [i32; 3],
+    pub cave_back_style: [u8; 4],
+    pub tree_tops: [u8; 13],
+    pub max_raining: f32,
+    pub flags: WorldFlags,
+    pub sundial_cooldown: u8,
+    pub moondial_cooldown: u8,
+    /// The ore chosen for copper, iron, silver, gold, cobalt, mythril and adamantite,
+    /// as tile ids
+    pub ore_tiers: [i16; 7],
+    /// The invasion currently happening, 0 if none
+    pub invasion_type: i8,
+    /// The Steam lobby id, 0 for dedicated servers
+    pub lobby_id: u64,
+    pub sandstorm_severity: f32,
+}
+
+/// Declares [`WorldFlags`] with the byte and bit each flag is sent in
+macro_rules! world_flags {
+    ($($(#[$doc:meta])* $name:ident: $byte:literal, $bit:literal;)*) => {
+        /// The bit flags of the world info: downed bosses, events, unlocks and special seeds
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        pub struct WorldFlags {
+            $($(#[$doc])* pub $name: bool,)*
+        }
+
+        impl WorldFlags {
+            pub const fn from_bytes(bytes: [u8; 10]) -> Self {
+                Self {
+                    $($name: bytes[$byte] & (1 << $bit) != 0,)*
+                }
+            }
+
+            pub const fn to_bytes(self) -> [u8; 10] {
+                let mut bytes = [0u8; 10];
+                $(if self.$name {
+                    bytes[$byte] |= 1 << $bit;
+                })*
+                bytes
+            }
+        }
+    };
+}
+
+world_flags! {
+    shadow_orb_smashed: 0, 0;
+    downed_eye_of_cthulhu: 0, 1;
+    /// Eater of Worlds or Brain of Cthulhu
+    downed_evil_boss: 0, 2;
+    downed_skeletron: 0, 3;
+    /// Set once the Wall of Flesh has been defeated
+    hardmode: 0, 4;
+    downed_clown: 0, 5;
+    server_side_characters: 0, 6;
+    downed_plantera: 0, 7;
+    downed_destroyer: 1, 0;
+    downed_twins: 1, 1;
+    downed_skeletron_prime: 1, 2;
+    downed_any_mech: 1, 3;
+    cloud_background_active: 1, 4;
+    crimson: 1, 5;
+    pumpkin_moon: 1, 6;
+    frost_moon: 1, 7;
+    fast_forward_time_to_dawn: 2, 1;
+    slime_rain: 2, 2;
+    downed_king_slime: 2, 3;
+    downed_queen_bee: 2, 4;
+    downed_duke_fishron: 2, 5;
+    downed_martians: 2, 6;
+    downed_lunatic_cultist: 2, 7;
+    downed_moon_lord: 3, 0;
+    downed_pumpking: 3, 1;
+    downed_mourning_wood: 3, 2;
+    downed_ice_queen: 3, 3;
+    downed_santa_nk1: 3, 4;
+    downed_everscream: 3, 5;
+    downed_golem: 3, 6;
+    party_is_up: 3, 7;
+    downed_pirates: 4, 0;
+    downed_frost_legion: 4, 1;
+    downed_goblins: 4, 2;
+    sandstorm_happening: 4, 3;
+    old_ones_army_ongoing: 4, 4;
+    downed_old_ones_army_tier_1: 4, 5;
+    downed_old_ones_army_tier_2: 4, 6;
+    downed_old_ones_army_tier_3: 4, 7;
+    combat_book_used: 5, 0;
+    lanterns_up: 5, 1;
+    downed_solar_pillar: 5, 2;
+    downed_vortex_pillar: 5, 3;
+    downed_nebula_pillar: 5, 4;
+    downed_stardust_pillar: 5, 5;
+    force_halloween_for_today: 5, 6;
+    force_xmas_for_today: 5, 7;
+    bought_cat: 6, 0;
+    bought_dog: 6, 1;
+    bought_bunny: 6, 2;
+    free_cake: 6, 3;
+    /// The "drunk world" seed
+    drunk_world: 6, 4;
+    downed_empress_of_light: 6, 5;
+    downed_queen_slime: 6, 6;
+    /// The "for the worthy" seed
+    for_the_worthy: 6, 7;
+    /// The "celebrationmk10" seed
+    tenth_anniversary_world: 7, 0;
+    /// The "the constant" seed
+    dont_starve_world: 7, 1;
+    downed_deerclops: 7, 2;
+    /// The "not the bees" seed
+    not_the_bees_world: 7, 3;
+    /// The "don't dig up" seed
+    remix_world: 7, 4;
+    unlocked_blue_slime: 7, 5;
+    combat_book_volume_two_used: 7, 6;
+    peddlers_satchel_used: 7, 7;
+    unlocked_green_slime: 8, 0;
+    unlocked_old_slime: 8, 1;
+    unlocked_purple_slime: 8, 2;
+    unlocked_rainbow_slime: 8, 3;
+    unlocked_red_slime: 8, 4;
+    unlocked_yellow_slime: 8, 5;
+    unlocked_copper_slime: 8, 6;
+    fast_forward_time_to_dusk: 8, 7;
+    /// The "no traps" seed
+    no_traps_world: 9, 0;
+    /// The "get fixed boi" (zenith) seed
+    zenith_world: 9, 1;
+    unlocked_truffle: 9, 2;
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 9, direction = ServerToClient)]
+pub struct S2CStatusText {
+    pub status_max: i32,
+    pub text: NetworkText,
+    pub flags: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 10, direction = ServerToClient)]
+pub struct S2CSendSection {
+    pub compressed: bool,
+    #[packet(rest)]
+    pub data: Vec<u8>,
+}
+
+impl S2CSendSection {
+    /// Decompresses and decodes the tiles in this section
+    pub fn tiles(&self) -> error::Result<TileGrid> {
+        if self.compressed {
+            TileGrid::read_section(&mut DeflateDecoder::new(self.data.as_slice()))
+        } else {
+            TileGrid::read_section(&mut self.data.as_slice())
+        }
+    }
+
+    /// Where the section is and how large it is, without decoding its tiles
+    pub fn bounds(&self) -> error::Result<Bounds> {
+        if self.compressed {
+            TileGrid::read_section_bounds(&mut DeflateDecoder::new(self.data.as_slice()))
+        } else {
+            TileGrid::read_section_bounds(&mut self.data.as_slice())
+        }
     }
 }
 
-// Packet ID: 2
-pub struct S2CFatalError {
-    pub error: String,
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 11, direction = ServerToClient)]
+pub struct S2CTileFrameSection {
+    pub start_x: i16,
+    pub start_y: i16,
+    pub end_x: i16,
+    pub end_y: i16,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 12, direction = ServerToClient)]
+pub struct S2CPlayerSpawn {
+    pub slot: u8,
+    pub spawn_x: i16,
+    pub spawn_y: i16,
+    pub respawn_timer: i32,
+    pub deaths_pve: i16,
+    pub deaths_pvp: i16,
+    pub context: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 14, direction = ServerToClient)]
+pub struct S2CPlayerActive {
+    pub slot: u8,
+    pub active: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 16, direction = ServerToClient)]
+pub struct S2CPlayerHealth {
+    pub slot: u8,
+    pub stat_life: i16,
+    pub stat_life_max: i16,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 21, direction = ServerToClient)]
+pub struct S2CSyncItem {
+    pub item_id: i16,
+    pub position: Vector2,
+    pub velocity: Vector2,
+    pub stack: i16,
+    pub prefix_id: u8,
+    pub no_delay: u8,
+    pub net_id: i16,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 42, direction = ServerToClient)]
+pub struct S2CPlayerMana {
+    pub slot: u8,
+    pub stat_mana: i16,
+    pub stat_mana_max: i16,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 49, direction = ServerToClient)]
+pub struct S2CCompleteConnectionAndSpawn;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct S2CPlayerBuffs {
+    pub slot: u8,
+    /// The buff type in each buff slot, 0 for an empty one
+    pub buffs: Vec<u16>,
+}
+
+impl TerrariaPacket for S2CPlayerBuffs {
+    const PACKET_ID: u8 = 50;
+    const DIRECTION: Direction = Direction::ServerToClient;
+
+    fn serialize(self) -> error::Result<Vec<u8>> {
+        write_buffs(self.slot, &self.buffs)
+    }
+
+    fn deserialize(bytes: Vec<u8>) -> error::Result<Box<Self>> {
+        let (slot, buffs) = read_buffs(bytes)?;
+        Ok(Box::new(Self { slot, buffs }))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 57, direction = ServerToClient)]
+pub struct S2CTileCounts {
+    pub good: u8,
+    pub evil: u8,
+    pub blood: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 74, direction = ServerToClient)]
+pub struct S2CAnglerQuest {
+    pub quest: u8,
+    pub completed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 82, direction = ServerToClient)]
+pub struct S2CNetModule {
+    pub module_id: u16,
+    #[packet(rest)]
+    pub body: Vec<u8>,
+}
+
+impl S2CNetModule {
+    /// Decodes the module body based on the module id
+    pub fn decode(&self) -> error::Result<NetModule> {
+        NetModule::deserialize(self.module_id, &self.body)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 83, direction = ServerToClient)]
+pub struct S2CKillCount {
+    pub npc_type: i16,
+    pub count: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 101, direction = ServerToClient)]
+pub struct S2CTowerShields {
+    pub solar: u16,
+    pub vortex: u16,
+    pub nebula: u16,
+    pub stardust: u16,
 }
 
-impl S2CTerrariaPacket for S2CFatalError {
-    fn deserialize(bytes: Vec<u8>) -> io::Result<Box<Self>> {
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 103, direction = ServerToClient)]
+pub struct S2CMoonLordCountdown {
+    pub countdown: i32,
+    pub max_countdown: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 129, direction = ServerToClient)]
+pub struct S2CFinishedConnectingToServer;
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 139, direction = ServerToClient)]
+pub struct S2CSetCountsAsHost {
+    pub slot: u8,
+    pub counts_as_host: bool,
+}
+
+/// A mod a tModLoader client needs to join the server
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModInfo {
+    pub name: String,
+    pub version: String,
+    /// SHA-1 hash of the .tmod file
+    pub hash: [u8; 20],
+    /// Whether the mod browser signature of the .tmod file is valid
+    pub signed: bool,
+    /// Server-side configs as (name, JSON) pairs
+    pub configs: Vec<(String, String)>,
+}
+
+/// Only sent by tModLoader servers
+#[derive(Clone, Debug, PartialEq)]
+pub struct S2CSyncMods {
+    pub allow_vanilla_clients: bool,
+    pub mods: Vec<ModInfo>,
+}
+
+impl TerrariaPacket for S2CSyncMods {
+    const PACKET_ID: u8 = 251;
+    const DIRECTION: Direction = Direction::ServerToClient;
+
+    fn serialize(self) -> error::Result<Vec<u8>> {
+        let count = |len: usize| i32::try_from(len).map_err(|_| ProtocolError::TooLarge("Mods"));
+        let mut packet = vec![u8::from(self.allow_vanilla_clients)];
+        packet.write_i32::<LittleEndian>(count(self.mods.len())?)?;
+        for m in self.mods {
+            packet.write_terraria_string(m.name)?;
+            packet.write_terraria_string(m.version)?;
+            packet.extend_from_slice(&m.hash);
+            packet.push(u8::from(m.signed));
+            packet.write_i32::<LittleEndian>(count(m.configs.len())?)?;
+            for (name, json) in m.configs {
+                packet.write_terraria_string(name)?;
+                packet.write_terraria_string(json)?;
+            }
+        }
+        Ok(packet)
+    }
+
+    fn deserialize(bytes: Vec<u8>) -> error::Result<Box<Self>> {
         let mut c = Cursor::new(bytes);
-        let error = c.read_terraria_string()?;
-        Ok(Box::new(Self { error }))
+        let allow_vanilla_clients = c.read_u8()? != 0;
+        let mod_count = c.read_i32::<LittleEndian>()?;
+        let mut mods = vec![];
+        for _ in 0..mod_count {
+            let name = c.read_terraria_string()?;
+            let version = c.read_terraria_string()?;
+            let mut hash = [0u8; 20];
+            c.read_exact(&mut hash)?;
+            let signed = c.read_u8()? != 0;
+            let config_count = c.read_i32::<LittleEndian>()?;
+            let mut configs = vec![];
+            for _ in 0..config_count {
+                configs.push((c.read_terraria_string()?, c.read_terraria_string()?));
+            }
+            mods.push(ModInfo {
+                name,
+                version,
+                hash,
+                signed,
+                configs,
+            });
+        }
+        Ok(Box::new(Self {
+            allow_vanilla_clients,
+            mods,
+        }))
     }
 }
 
-// Packet ID: 37
-pub struct S2CPasswordRequired;
+/// Any packet a server can send, decoded based on its packet id
+#[derive(Clone, Debug, PartialEq)]
+pub enum S2CPacket {
+    FatalError(S2CFatalError),
+    ConnectionApproved(S2CConnectionApproved),
+    PlayerInfo(S2CPlayerInfo),
+    WorldInfo(S2CWorldInfo),
+    StatusText(S2CStatusText),
+    SendSection(S2CSendSection),
+    TileFrameSection(S2CTileFrameSection),
+    PlayerSpawn(S2CPlayerSpawn),
+    PlayerActive(S2CPlayerActive),
+    PlayerHealth(S2CPlayerHealth),
+    SyncItem(S2CSyncItem),
+    PasswordRequired(S2CPasswordRequired),
+    PlayerMana(S2CPlayerMana),
+    CompleteConnectionAndSpawn(S2CCompleteConnectionAndSpawn),
+    PlayerBuffs(S2CPlayerBuffs),
+    TileCounts(S2CTileCounts),
+    AnglerQuest(S2CAnglerQuest),
+    NetModule(S2CNetModule),
+    KillCount(S2CKillCount),
+    TowerShields(S2CTowerShields),
+    MoonLordCountdown(S2CMoonLordCountdown),
+    FinishedConnectingToServer(S2CFinishedConnectingToServer),
+    SetCountsAsHost(S2CSetCountsAsHost),
+    SyncMods(S2CSyncMods),
+    Unknown { id: u8, body: Vec<u8> },
+}
+
+impl S2CPacket {
+    /// Decodes a packet body based on the packet id
+    pub fn deserialize(id: u8, body: Vec<u8>) -> error::Result<Self> {
+        Ok(match id {
+            2 => Self::FatalError(*S2CFatalError::deserialize(body)?),
+            3 => Self::ConnectionApproved(*S2CConnectionApproved::deserialize(body)?),
+            4 => Self::PlayerInfo(*S2CPlayerInfo::deserialize(body)?),
+            7 => Self::WorldInfo(*S2CWorldInfo::deserialize(body)?),
+            9 => Self::StatusText(*S2CStatusText::deserialize(body)?),
+            10 => Self::SendSection(*S2CSendSection::deserialize(body)?),
+            11 => Self::TileFrameSection(*S2CTileFrameSection::deserialize(body)?),
+            12 => Self::PlayerSpawn(*S2CPlayerSpawn::deserialize(body)?),
+            14 => Self::PlayerActive(*S2CPlayerActive::deserialize(body)?),
+            16 => Self::PlayerHealth(*S2CPlayerHealth::deserialize(body)?),
+            21 => Self::SyncItem(*S2CSyncItem::deserialize(body)?),
+            37 => Self::PasswordRequired(*S2CPasswordRequired::deserialize(body)?),
+            42 => Self::PlayerMana(*S2CPlayerMana::deserialize(body)?),
+            49 => {
+                Self::CompleteConnectionAndSpawn(*S2CCompleteConnectionAndSpawn::deserialize(body)?)
+            }
+            50 => Self::PlayerBuffs(*S2CPlayerBuffs::deserialize(body)?),
+            57 => Self::TileCounts(*S2CTileCounts::deserialize(body)?),
+            74 => Self::AnglerQuest(*S2CAnglerQuest::deserialize(body)?),
+            82 => Self::NetModule(*S2CNetModule::deserialize(body)?),
+            83 => Self::KillCount(*S2CKillCount::deserialize(body)?),
+            101 => Self::TowerShields(*S2CTowerShields::deserialize(body)?),
+            103 => Self::MoonLordCountdown(*S2CMoonLordCountdown::deserialize(body)?),
+            129 => {
+                Self::FinishedConnectingToServer(*S2CFinishedConnectingToServer::deserialize(body)?)
+            }
+            139 => Self::SetCountsAsHost(*S2CSetCountsAsHost::deserialize(body)?),
+            251 => Self::SyncMods(*S2CSyncMods::deserialize(body)?),
+            id => Self::Unknown { id, body },
+        })
+    }
+
+    /// The packet id of this packet
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::FatalError(_) => 2,
+            Self::ConnectionApproved(_) => 3,
+            Self::PlayerInfo(_) => 4,
+            Self::WorldInfo(_) => 7,
+            Self::StatusText(_) => 9,
+            Self::SendSection(_) => 10,
+            Self::TileFrameSection(_) => 11,
+            Self::PlayerSpawn(_) => 12,
+            Self::PlayerActive(_) => 14,
+            Self::PlayerHealth(_) => 16,
+            Self::SyncItem(_) => 21,
+            Self::PasswordRequired(_) => 37,
+            Self::PlayerMana(_) => 42,
+            Self::CompleteConnectionAndSpawn(_) => 49,
+            Self::PlayerBuffs(_) => 50,
+            Self::TileCounts(_) => 57,
+            Self::AnglerQuest(_) => 74,
+            Self::NetModule(_) => 82,
+            Self::KillCount(_) => 83,
+            Self::TowerShields(_) => 101,
+            Self::MoonLordCountdown(_) => 103,
+            Self::FinishedConnectingToServer(_) => 129,
+            Self::SetCountsAsHost(_) => 139,
+            Self::SyncMods(_) => 251,
+            Self::Unknown { id, .. } => *id,
+        }
+    }
+}
+
+/// Any packet a client can send, decoded based on its packet id
+#[derive(Clone, Debug, PartialEq)]
+pub enum C2SPacket {
+    Connect(C2SConnect),
+    PlayerAppearance(C2SPlayerAppearance),
+    SetInvSlot(C2SSetInvSlot),
+    RequestWorldInfo(C2SRequestWorldInfo),
+    RequestInitialTileData(C2SRequestInitialTileData),
+    SpawnPlayer(C2SSpawnPlayer),
+    SetHealth(C2SSetHealth),
+    SendPassword(C2SSendPassword),
+    SetMana(C2SSetMana),
+    SetBuffs(C2SSetBuffs),
+    ClientUuid(C2SClientUuid),
+    NetMessageCommand(C2SNetMessageCommand),
+    Unknown { id: u8, body: Vec<u8> },
+}
 
-impl S2CTerrariaPacket for S2CPasswordRequired {
-    fn deserialize(_: Vec<u8>) -> io::Result<Box<Self>> {
-        Ok(Box::new(Self))
+impl C2SPacket {
+    /// Decodes a packet body based on the packet id
+    pub fn deserialize(id: u8, body: Vec<u8>) -> error::Result<Self> {
+        Ok(match id {
+            1 => Self::Connect(*C2SConnect::deserialize(body)?),
+            4 => Self::PlayerAppearance(*C2SPlayerAppearance::deserialize(body)?),
+            5 => Self::SetInvSlot(*C2SSetInvSlot::deserialize(body)?),
+            6 => Self::RequestWorldInfo(*C2SRequestWorldInfo::deserialize(body)?),
+            8 => Self::RequestInitialTileData(*C2SRequestInitialTileData::deserialize(body)?),
+            12 => Self::SpawnPlayer(*C2SSpawnPlayer::deserialize(body)?),
+            16 => Self::SetHealth(*C2SSetHealth::deserialize(body)?),
+            38 => Self::SendPassword(*C2SSendPassword::deserialize(body)?),
+            42 => Self::SetMana(*C2SSetMana::deserialize(body)?),
+            50 => Self::SetBuffs(*C2SSetBuffs::deserialize(body)?),
+            68 => Self::ClientUuid(*C2SClientUuid::deserialize(body)?),
+            82 => Self::NetMessageCommand(*C2SNetMessageCommand::deserialize(body)?),
+            id => Self::Unknown { id, body },
+        })
+    }
+
+    /// The packet id of this packet
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::Connect(_) => 1,
+            Self::PlayerAppearance(_) => 4,
+            Self::SetInvSlot(_) => 5,
+            Self::RequestWorldInfo(_) => 6,
+            Self::RequestInitialTileData(_) => 8,
+            Self::SpawnPlayer(_) => 12,
+            Self::SetHealth(_) => 16,
+            Self::SendPassword(_) => 38,
+            Self::SetMana(_) => 42,
+            Self::SetBuffs(_) => 50,
+            Self::ClientUuid(_) => 68,
+            Self::NetMessageCommand(_) => 82,
+            Self::Unknown { id, .. } => *id,
+        }
     }
 }
 
-#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Difficulty {
     Normal,
     Mediumcore,
     Hardcore,
+    Journey,
+}
+
+impl Difficulty {
+    /// Reads the difficulty from the first bits byte of packet 4
+    pub const fn from_bits(bits: u8) -> Self {
+        if bits & 0b0000_1000 != 0 {
+            Self::Journey
+        } else if bits & 0b0000_0010 != 0 {
+            Self::Hardcore
+        } else if bits & 0b0000_0001 != 0 {
+            Self::Mediumcore
+        } else {
+            Self::Normal
+        }
+    }
+
+    pub const fn to_bits(self) -> u8 {
+        match self {
+            Self::Normal => 0,
+            Self::Mediumcore => 0b0000_0001,
+            Self::Hardcore => 0b0000_0010,
+            Self::Journey => 0b0000_1000,
+        }
+    }
 }
 
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 4, direction = ClientToServer)]
 pub struct C2SPlayerAppearance {
     pub slot: u8,
     pub skin_variant: u8,
     pub hair: u8,
     pub name: String,
     pub hair_dye: u8,
+    #[packet(bitmask)]
     pub hide_visible_accessory: [bool; 10],
     pub hide_misc: bool,
     pub hair_color: Rgb,
@@ -124,169 +807,103 @@ pub struct C2SPlayerAppearance {
     pub pants_color: Rgb,
     pub shoe_color: Rgb,
 
+    #[packet(bits = "difficulty")]
     pub difficulty: Difficulty,
+    #[packet(bits = "difficulty", bit = 2)]
     pub extra_accessory: bool,
 
+    #[packet(bits = "torches", bit = 0)]
     pub using_biome_torches: bool,
+    #[packet(bits = "torches", bit = 1)]
     pub happy_fun_torch_time: bool,
+    #[packet(bits = "torches", bit = 2)]
     pub unlocked_biome_torches: bool,
+    #[packet(bits = "torches", bit = 3)]
     pub unlocked_super_cart: bool,
+    #[packet(bits = "torches", bit = 4)]
     pub enabled_super_cart: bool,
 
+    #[packet(bits = "upgrades", bit = 0)]
     pub used_aegis_crystal: bool,
+    #[packet(bits = "upgrades", bit = 1)]
     pub used_aegis_fruit: bool,
+    #[packet(bits = "upgrades", bit = 2)]
     pub used_arcane_crystal: bool,
+    #[packet(bits = "upgrades", bit = 3)]
     pub used_galaxy_pearl: bool,
+    #[packet(bits = "upgrades", bit = 4)]
     pub used_gummy_worm: bool,
+    #[packet(bits = "upgrades", bit = 5)]
     pub used_ambrosia: bool,
+    #[packet(bits = "upgrades", bit = 6)]
     pub ate_artisan_bread: bool,
 }
 
-impl C2STerrariaPacket for C2SPlayerAppearance {
-    const PACKET_ID: u8 = 4;
-
-    fn serialize(self) -> Vec<u8> {
-        let mut packet = vec![];
-        packet.push(self.slot);
-        packet.push(self.skin_variant);
-        packet.push(self.hair);
-        packet.write_terraria_string(self.name).unwrap();
-        packet.push(self.hair_dye);
-        let mut hva = 0u16;
-        for i in 0..10 {
-            if self.hide_visible_accessory[i] {
-                hva |= 1 << i;
-            }
-        }
-        packet.write_u16::<LittleEndian>(hva).unwrap();
-        packet.push(self.hide_misc as u8);
-        packet.write_terraria_rgb(self.hair_color).unwrap();
-        packet.write_terraria_rgb(self.skin_color).unwrap();
-        packet.write_terraria_rgb(self.eye_color).unwrap();
-        packet.write_terraria_rgb(self.shirt_color).unwrap();
-        packet.write_terraria_rgb(self.undershirt_color).unwrap();
-        packet.write_terraria_rgb(self.pants_color).unwrap();
-        packet.write_terraria_rgb(self.shoe_color).unwrap();
-
-        let mut bits1 = 0u8;
-        match self.difficulty {
-            Difficulty::Normal => bits1 |= 0b1000_0000,
-            Difficulty::Mediumcore => bits1 |= 0b0100_0000,
-            Difficulty::Hardcore => bits1 |= 0b0001_0000,
-        }
-        if self.extra_accessory {
-            bits1 |= 0b0010_0000;
-        }
-        packet.push(bits1);
-
-        let mut bits2 = 0u8;
-        if self.unlocked_biome_torches {
-            bits2 |= 0b1000_0000;
-        }
-        if self.happy_fun_torch_time {
-            bits2 |= 0b0100_0000;
-        }
-        if self.unlocked_biome_torches {
-            bits2 |= 0b0010_0000;
-        }
-        if self.unlocked_super_cart {
-            bits2 |= 0b0001_0000;
-        }
-        if self.enabled_super_cart {
-            bits2 |= 0b0000_1000;
-        }
-        packet.push(bits2);
-
-        let mut bits3 = 0u8;
-        if self.used_aegis_crystal {
-            bits3 |= 0b1000_0000;
-        }
-        if self.used_aegis_fruit {
-            bits3 |= 0b0100_0000;
-        }
-        if self.used_arcane_crystal {
-            bits3 |= 0b0010_0000;
-        }
-        if self.used_galaxy_pearl {
-            bits3 |= 0b0001_0000;
-        }
-        if self.used_gummy_worm {
-            bits3 |= 0b0000_1000;
-        }
-        if self.used_ambrosia {
-            bits3 |= 0b0000_0100;
-        }
-        if self.ate_artisan_bread {
-            bits3 |= 0b0000_0010;
-        }
-        packet.push(bits3);
-        packet
-    }
-}
-
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 16, direction = ClientToServer)]
 pub struct C2SSetHealth {
     pub slot: u8,
     pub stat_life: i16,
     pub stat_life_max: i16,
 }
 
-impl C2STerrariaPacket for C2SSetHealth {
-    const PACKET_ID: u8 = 16;
-
-    fn serialize(self) -> Vec<u8> {
-        let mut packet = vec![];
-        packet.push(self.slot);
-        packet.write_i16::<LittleEndian>(self.stat_life).unwrap();
-        packet
-            .write_i16::<LittleEndian>(self.stat_life_max)
-            .unwrap();
-        packet
-    }
-}
-
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 42, direction = ClientToServer)]
 pub struct C2SSetMana {
     pub slot: u8,
     pub stat_mana: i16,
     pub stat_mana_max: i16,
 }
 
-impl C2STerrariaPacket for C2SSetMana {
-    const PACKET_ID: u8 = 42;
-
-    fn serialize(self) -> Vec<u8> {
-        let mut packet = vec![];
-        packet.push(self.slot);
-        packet.write_i16::<LittleEndian>(self.stat_mana).unwrap();
-        packet
-            .write_i16::<LittleEndian>(self.stat_mana_max)
-            .unwrap();
-        packet
-    }
-}
+/// How many buffs a player can have at once
+pub const MAX_BUFFS: usize = 44;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct C2SSetBuffs {
     pub slot: u8,
+    /// The buff type in each buff slot, 0 for an empty one
     pub buffs: Vec<u16>,
 }
 
-impl C2STerrariaPacket for C2SSetBuffs {
+impl TerrariaPacket for C2SSetBuffs {
     const PACKET_ID: u8 = 50;
+    const DIRECTION: Direction = Direction::ClientToServer;
 
-    fn serialize(self) -> Vec<u8> {
-        let mut packet = vec![];
-        if self.buffs.len() > 44 {
-            panic!("Too many buffs")
-        }
-        for b in self.buffs.iter() {
-            packet.write_u16::<LittleEndian>(*b).unwrap();
-        }
-        for _ in 0..(44 - self.buffs.len()) {
-            packet.write_u16::<LittleEndian>(0).unwrap();
-        }
-        packet
+    fn serialize(self) -> error::Result<Vec<u8>> {
+        write_buffs(self.slot, &self.buffs)
+    }
+
+    fn deserialize(bytes: Vec<u8>) -> error::Result<Box<Self>> {
+        let (slot, buffs) = read_buffs(bytes)?;
+        Ok(Box::new(Self { slot, buffs }))
+    }
+}
+
+/// Writes the player slot and all [`MAX_BUFFS`] buff slots, the ones after `buffs` empty
+fn write_buffs(slot: u8, buffs: &[u16]) -> error::Result<Vec<u8>> {
+    if buffs.len() > MAX_BUFFS {
+        return Err(ProtocolError::TooLarge("Buffs"));
     }
+    let mut packet = vec![slot];
+    for b in buffs.iter().chain([0; MAX_BUFFS].iter()).take(MAX_BUFFS) {
+        packet.write_u16::<LittleEndian>(*b)?;
+    }
+    Ok(packet)
 }
 
+/// Reads the player slot and the buff slots, with the empty slots at the end left out.
+/// Empty slots between buffs stay zeroes, so every buff keeps its slot
+fn read_buffs(bytes: Vec<u8>) -> error::Result<(u8, Vec<u16>)> {
+    let mut c = Cursor::new(bytes);
+    let slot = c.read_u8()?;
+    let mut buffs = [0u16; MAX_BUFFS];
+    c.read_u16_into::<LittleEndian>(&mut buffs)?;
+    let len = buffs.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    Ok((slot, buffs[..len].to_vec()))
+}
+
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 5, direction = ClientToServer)]
 pub struct C2SSetInvSlot {
     pub slot: u8,
     pub inv_slot: u8,
@@ -295,73 +912,71 @@ pub struct C2SSetInvSlot {
     pub net_id: i16,
 }
 
-impl C2STerrariaPacket for C2SSetInvSlot {
-    const PACKET_ID: u8 = 5;
-
-    fn serialize(self) -> Vec<u8> {
-        let mut packet = vec![];
-        packet.push(self.slot);
-        packet.push(self.inv_slot);
-        packet.extend_from_slice(&self.stack.to_le_bytes());
-        packet.push(self.prefix_id);
-        packet.extend_from_slice(&self.net_id.to_le_bytes());
-        packet
-    }
-}
-
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 6, direction = ClientToServer)]
 pub struct C2SRequestWorldInfo;
 
-impl C2STerrariaPacket for C2SRequestWorldInfo {
-    const PACKET_ID: u8 = 6;
-
-    fn serialize(self) -> Vec<u8> {
-        vec![]
-    }
+/// The answer to [`S2CPasswordRequired`]
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 38, direction = ClientToServer)]
+pub struct C2SSendPassword {
+    pub password: String,
 }
 
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 68, direction = ClientToServer)]
 pub struct C2SClientUuid {
     pub uuid: String,
 }
 
-impl C2STerrariaPacket for C2SClientUuid {
-    const PACKET_ID: u8 = 68;
-
-    fn serialize(self) -> Vec<u8> {
-        let mut packet = vec![];
-        packet.write_terraria_string(self.uuid).unwrap();
-        packet
-    }
-}
-
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 8, direction = ClientToServer)]
 pub struct C2SRequestInitialTileData {
     pub x: i32,
     pub y: i32,
 }
 
-impl C2STerrariaPacket for C2SRequestInitialTileData {
-    const PACKET_ID: u8 = 8;
-
-    fn serialize(self) -> Vec<u8> {
-        let mut packet = vec![];
-        packet.extend_from_slice(&self.x.to_le_bytes());
-        packet.extend_from_slice(&self.y.to_le_bytes());
-        packet
-    }
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 12, direction = ClientToServer)]
+pub struct C2SSpawnPlayer {
+    pub slot: u8,
+    pub spawn_x: i16,
+    pub spawn_y: i16,
+    pub respawn_timer: i32,
+    pub deaths_pve: i16,
+    pub deaths_pvp: i16,
+    pub context: u8,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct C2SNetMessageCommand {
     pub command: String,
     pub text: String,
 }
 
-impl C2STerrariaPacket for C2SNetMessageCommand {
+impl TerrariaPacket for C2SNetMessageCommand {
     const PACKET_ID: u8 = 82;
+    const DIRECTION: Direction = Direction::ClientToServer;
 
-    fn serialize(self) -> Vec<u8> {
+    fn serialize(self) -> error::Result<Vec<u8>> {
         let mut packet = vec![];
         packet.extend_from_slice(&1u16.to_le_bytes());
-        packet.write_terraria_string(self.command).unwrap();
-        packet.write_terraria_string(self.text).unwrap();
-        packet
+        packet.write_terraria_string(self.command)?;
+        packet.write_terraria_string(self.text)?;
+        Ok(packet)
+    }
+
+    fn deserialize(bytes: Vec<u8>) -> error::Result<Box<Self>> {
+        let mut c = Cursor::new(bytes);
+        let module_id = c.read_u16::<LittleEndian>()?;
+        if module_id != 1 {
+            return Err(ProtocolError::invalid_enum_value(
+                "chat command module",
+                module_id,
+            ));
+        }
+        let command = c.read_terraria_string()?;
+        let text = c.read_terraria_string()?;
+        Ok(Box::new(Self { command, text }))
     }
-}
\ No newline at end of file
+}
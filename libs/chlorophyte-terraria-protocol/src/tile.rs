@@ -0,0 +1,329 @@
+use crate::error;
+use crate::error::ProtocolError;
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::io::Read;
+
+/// The number of tile types in 1.4.4.9
+pub const TILE_COUNT: u16 = 693;
+/// The number of wall types in 1.4.4.9
+pub const WALL_COUNT: u16 = 347;
+
+/// The most tiles [`TileGrid::stitch`] puts into one grid, enough for a few hundred sections
+pub const MAX_GRID_TILES: usize = 1 << 22;
+
+/// The most tiles a section can have, a little more than the 200x150 tiles vanilla sends
+pub const MAX_SECTION_TILES: usize = 256 * 256;
+
+/// Tile types that have `Main.tileFrameImportant` set in 1.4.4.9.
+/// Their frame is sent along with the tile, as the client can't work it out on its own
+pub const FRAME_IMPORTANT: &[u16] = &[
+    3, 4, 5, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 24, 26, 27, 28, 29, 31, 33, 34, 35,
+    36, 42, 49, 50, 55, 61, 71, 72, 73, 74, 77, 78, 79, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91,
+    92, 93, 94, 95, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 110, 113, 114, 125, 126,
+    128, 129, 132, 133, 134, 135, 136, 137, 138, 139, 141, 142, 143, 144, 149, 165, 171, 172, 173,
+    174, 178, 184, 185, 186, 187, 201, 207, 209, 210, 212, 215, 216, 217, 218, 219, 220, 227, 228,
+    231, 233, 235, 236, 237, 238, 239, 240, 241, 242, 243, 244, 245, 246, 247, 254, 269, 270, 271,
+    275, 276, 277, 278, 279, 280, 281, 282, 283, 285, 286, 287, 288, 289, 290, 291, 292, 293, 294,
+    295, 296, 297, 298, 299, 300, 301, 302, 303, 304, 305, 306, 307, 308, 309, 310, 314, 316, 317,
+    318, 319, 320, 323, 324, 334, 335, 337, 338, 339, 349, 354, 355, 356, 358, 359, 360, 361, 362,
+    363, 364, 372, 373, 374, 375, 376, 377, 378, 380, 386, 387, 388, 389, 390, 391, 392, 393, 394,
+    395, 405, 406, 410, 411, 412, 413, 414, 419, 420, 423, 424, 425, 427, 428, 429, 435, 436, 437,
+    438, 439, 440, 441, 442, 443, 444, 445, 452, 453, 454, 455, 456, 457, 461, 462, 463, 464, 465,
+    466, 467, 468, 469, 470, 471, 475, 476, 480, 484, 485, 486, 487, 488, 489, 490, 491, 493, 494,
+    497, 499, 505, 506, 509, 510, 511, 518, 519, 520, 521, 522, 523, 524, 525, 526, 527, 529, 530,
+    531, 532, 533, 538, 542, 543, 544, 545, 547, 548, 549, 550, 551, 552, 553, 554, 555, 556, 558,
+    559, 560, 564, 565, 567, 568, 569, 570, 571, 572, 573, 579, 580, 581, 582, 583, 584, 585, 586,
+    587, 588, 589, 590, 591, 592, 593, 594, 595, 596, 597, 598, 599, 600, 601, 602, 603, 604, 605,
+    606, 607, 608, 609, 610, 611, 612, 613, 614, 615, 616, 617, 619, 620, 621, 622, 623, 624, 629,
+    630, 631, 632, 634, 637, 639, 640, 642, 643, 644, 645, 646, 647, 648, 649, 650, 651, 652, 653,
+    654, 656, 657, 658, 660, 663, 664, 665, 669, 670, 671, 672, 673, 674, 675, 676, 677, 678, 679,
+    680, 681, 682, 683, 684, 685, 686, 687, 688, 689, 690, 691, 692,
+];
+
+pub fn is_frame_important(tile_type: u16) -> bool {
+    FRAME_IMPORTANT.binary_search(&tile_type).is_ok()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LiquidType {
+    #[default]
+    Water,
+    Lava,
+    Honey,
+    Shimmer,
+}
+
+/// A single tile of the world
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tile {
+    /// The block or furniture on this tile, `None` if there's nothing
+    pub tile_type: Option<u16>,
+    /// The frame of frame important tiles
+    pub frame: Option<(i16, i16)>,
+    pub tile_color: u8,
+    /// 0 if there's no wall
+    pub wall: u16,
+    pub wall_color: u8,
+    /// How full of liquid the tile is, 0-255
+    pub liquid: u8,
+    pub liquid_type: LiquidType,
+    pub wire_red: bool,
+    pub wire_blue: bool,
+    pub wire_green: bool,
+    pub wire_yellow: bool,
+    pub actuator: bool,
+    /// Actuated tiles can be walked through
+    pub inactive: bool,
+    /// 0 for full blocks, 1 for half blocks and 2-5 for the four slopes
+    pub slope: u8,
+    pub invisible_block: bool,
+    pub invisible_wall: bool,
+    pub fullbright_block: bool,
+    pub fullbright_wall: bool,
+}
+
+/// Where a grid is in the world and how large it is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// A rectangle of tiles at a position in the world
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TileGrid {
+    pub x: i32,
+    pub y: i32,
+    pub width: usize,
+    pub height: usize,
+    /// Row by row
+    tiles: Vec<Tile>,
+}
+
+impl TileGrid {
+    /// An empty grid with its top left corner at `x`, `y`
+    pub fn new(x: i32, y: i32, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            tiles: vec![Tile::default(); width * height],
+        }
+    }
+
+    pub const fn bounds(&self) -> Bounds {
+        Bounds {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// The tile at world position `x`, `y`, if it's in this grid
+    pub fn get(&self, x: i32, y: i32) -> Option<&Tile> {
+        self.index(x, y).map(|i| &self.tiles[i])
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut Tile> {
+        self.index(x, y).map(|i| &mut self.tiles[i])
+    }
+
+    /// The rows of the grid, from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[Tile]> {
+        self.tiles.chunks(self.width.max(1))
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let x = usize::try_from(i64::from(x) - i64::from(self.x)).ok()?;
+        let y = usize::try_from(i64::from(y) - i64::from(self.y)).ok()?;
+        (x < self.width && y < self.height).then(|| y * self.width + x)
+    }
+
+    /// Copies the tiles of `other` that overlap with this grid into it
+    pub fn paste(&mut self, other: &Self) {
+        for y in other.y..other.y + other.height as i32 {
+            for x in other.x..other.x + other.width as i32 {
+                if let (Some(tile), Some(target)) = (other.get(x, y), self.get_mut(x, y)) {
+                    *target = *tile;
+                }
+            }
+        }
+    }
+
+    /// An empty grid that covers all of `bounds`. Returns `None` if there are no bounds or the
+    /// grid would have more than `max_tiles` tiles
+    pub fn covering(bounds: &[Bounds], max_tiles: usize) -> Option<Self> {
+        let min_x = bounds.iter().map(|b| i64::from(b.x)).min()?;
+        let min_y = bounds.iter().map(|b| i64::from(b.y)).min()?;
+        let max_x = bounds
+            .iter()
+            .map(|b| i64::from(b.x) + b.width as i64)
+            .max()?;
+        let max_y = bounds
+            .iter()
+            .map(|b| i64::from(b.y) + b.height as i64)
+            .max()?;
+        let width = usize::try_from(max_x - min_x).ok()?;
+        let height = usize::try_from(max_y - min_y).ok()?;
+        if width.checked_mul(height)? > max_tiles {
+            return None;
+        }
+        Some(Self::new(
+            i32::try_from(min_x).ok()?,
+            i32::try_from(min_y).ok()?,
+            width,
+            height,
+        ))
+    }
+
+    /// Puts the sections together into one grid that covers all of them.
+    /// Parts of the grid no section covers are left empty.
+    /// Returns `None` if there are no sections or the grid would have more than
+    /// [`MAX_GRID_TILES`] tiles
+    pub fn stitch(sections: &[Self]) -> Option<Self> {
+        let bounds = sections.iter().map(Self::bounds).collect::<Vec<Bounds>>();
+        let mut grid = Self::covering(&bounds, MAX_GRID_TILES)?;
+        for section in sections {
+            grid.paste(section);
+        }
+        Some(grid)
+    }
+
+    /// Decodes the uncompressed body of packet 10: the position and size of the section,
+    /// followed by the run-length encoded tiles. The chests, signs and tile entities
+    /// after the tiles are not decoded
+    pub fn read_section<R: Read>(r: &mut R) -> error::Result<Self> {
+        let Bounds {
+            x,
+            y,
+            width,
+            height,
+        } = Self::read_section_bounds(r)?;
+        let mut grid = Self::new(x, y, width, height);
+        let mut i = 0;
+        while i < grid.tiles.len() {
+            let (tile, repeat) = read_tile(r)?;
+            let end = i + 1 + repeat;
+            if end > grid.tiles.len() {
+                return Err(ProtocolError::LengthMismatch(
+                    "Tile run goes past the end of the section",
+                ));
+            }
+            grid.tiles[i..end].fill(tile);
+            i = end;
+        }
+        Ok(grid)
+    }
+
+    /// Reads only the position and size at the start of the body of packet 10. Sections with
+    /// more than [`MAX_SECTION_TILES`] tiles are refused
+    pub fn read_section_bounds<R: Read>(r: &mut R) -> error::Result<Bounds> {
+        let x = r.read_i32::<LittleEndian>()?;
+        let y = r.read_i32::<LittleEndian>()?;
+        let width = r.read_i16::<LittleEndian>()?;
+        let height = r.read_i16::<LittleEndian>()?;
+        let (Ok(width), Ok(height)) = (usize::try_from(width), usize::try_from(height)) else {
+            return Err(ProtocolError::LengthMismatch("Section has a negative size"));
+        };
+        if width * height > MAX_SECTION_TILES {
+            return Err(ProtocolError::TooLarge("Section"));
+        }
+        // The tiles are addressed by i32 world coordinates, so the section has to fit into them
+        if x.checked_add(width as i32).is_none() || y.checked_add(height as i32).is_none() {
+            return Err(ProtocolError::TooLarge("Section position"));
+        }
+        Ok(Bounds {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+/// Reads one tile and how many times it's repeated after itself
+fn read_tile<R: Read>(r: &mut R) -> error::Result<(Tile, usize)> {
+    let mut tile = Tile::default();
+    let header1 = r.read_u8()?;
+    let mut header2 = 0;
+    let mut header3 = 0;
+    let mut header4 = 0;
+    if header1 & 0b0000_0001 != 0 {
+        header2 = r.read_u8()?;
+        if header2 & 0b0000_0001 != 0 {
+            header3 = r.read_u8()?;
+            if header3 & 0b0000_0001 != 0 {
+                header4 = r.read_u8()?;
+            }
+        }
+    }
+
+    if header1 & 0b0000_0010 != 0 {
+        let tile_type = if header1 & 0b0010_0000 != 0 {
+            r.read_u16::<LittleEndian>()?
+        } else {
+            u16::from(r.read_u8()?)
+        };
+        if tile_type >= TILE_COUNT {
+            return Err(ProtocolError::invalid_enum_value("tile type", tile_type));
+        }
+        if is_frame_important(tile_type) {
+            let frame_x = r.read_i16::<LittleEndian>()?;
+            let frame_y = r.read_i16::<LittleEndian>()?;
+            tile.frame = Some((frame_x, frame_y));
+        }
+        if header3 & 0b0000_1000 != 0 {
+            tile.tile_color = r.read_u8()?;
+        }
+        tile.tile_type = Some(tile_type);
+    }
+
+    if header1 & 0b0000_0100 != 0 {
+        tile.wall = u16::from(r.read_u8()?);
+        if header3 & 0b0001_0000 != 0 {
+            tile.wall_color = r.read_u8()?;
+        }
+    }
+
+    let liquid_bits = (header1 & 0b0001_1000) >> 3;
+    if liquid_bits != 0 {
+        tile.liquid = r.read_u8()?;
+        tile.liquid_type = match liquid_bits {
+            _ if header3 & 0b1000_0000 != 0 => LiquidType::Shimmer,
+            2 => LiquidType::Lava,
+            3 => LiquidType::Honey,
+            _ => LiquidType::Water,
+        };
+    }
+
+    tile.wire_red = header2 & 0b0000_0010 != 0;
+    tile.wire_blue = header2 & 0b0000_0100 != 0;
+    tile.wire_green = header2 & 0b0000_1000 != 0;
+    tile.slope = (header2 & 0b0111_0000) >> 4;
+
+    tile.actuator = header3 & 0b0000_0010 != 0;
+    tile.inactive = header3 & 0b0000_0100 != 0;
+    tile.wire_yellow = header3 & 0b0010_0000 != 0;
+    if header3 & 0b0100_0000 != 0 {
+        tile.wall |= u16::from(r.read_u8()?) << 8;
+    }
+    if tile.wall >= WALL_COUNT {
+        return Err(ProtocolError::invalid_enum_value("wall type", tile.wall));
+    }
+
+    tile.invisible_block = header4 & 0b0000_0010 != 0;
+    tile.invisible_wall = header4 & 0b0000_0100 != 0;
+    tile.fullbright_block = header4 & 0b0000_1000 != 0;
+    tile.fullbright_wall = header4 & 0b0001_0000 != 0;
+
+    let repeat = match (header1 & 0b1100_0000) >> 6 {
+        0 => 0,
+        1 => usize::from(r.read_u8()?),
+        _ => usize::from(r.read_u16::<LittleEndian>()?),
+    };
+    Ok((tile, repeat))
+}
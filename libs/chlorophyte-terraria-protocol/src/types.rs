@@ -1,51 +1,153 @@
-use byteorder::ReadBytesExt;
-use std::io;
+use crate::error;
+use crate::error::ProtocolError;
+use crate::network_text::{NetworkText, MAX_DEPTH};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Read, Write};
 
 pub type Rgb = (u8, u8, u8);
+pub type Vector2 = (f32, f32);
+
+/// A string can't be longer than the packet it's in
+pub const MAX_STRING_LEN: usize = u16::MAX as usize;
 
 #[allow(dead_code)]
 pub trait TerrariaTypesR {
-    fn read_terraria_string(&mut self) -> io::Result<String>;
-    fn read_terraria_rgb(&mut self) -> io::Result<Rgb>;
+    fn read_7bit_encoded_int(&mut self) -> error::Result<i32>;
+    fn read_terraria_string(&mut self) -> error::Result<String>;
+    fn read_network_text(&mut self) -> error::Result<NetworkText>;
+    fn read_terraria_rgb(&mut self) -> error::Result<Rgb>;
+    fn read_terraria_vector2(&mut self) -> error::Result<Vector2>;
 }
 
 pub trait TerrariaTypesW {
-    fn write_terraria_string(&mut self, value: String) -> io::Result<()>;
-    fn write_terraria_rgb(&mut self, value: Rgb) -> io::Result<()>;
+    fn write_7bit_encoded_int(&mut self, value: i32) -> error::Result<()>;
+    fn write_terraria_string(&mut self, value: String) -> error::Result<()>;
+    fn write_network_text(&mut self, value: NetworkText) -> error::Result<()>;
+    fn write_terraria_rgb(&mut self, value: Rgb) -> error::Result<()>;
+    fn write_terraria_vector2(&mut self, value: Vector2) -> error::Result<()>;
 }
 
 impl<T: Read> TerrariaTypesR for T {
-    fn read_terraria_string(&mut self) -> io::Result<String> {
-        let len = self.read_u8()? - 2;
-        let mut buf = vec![0u8; len as usize];
+    /// Reads an int the way .NET's `BinaryReader.Read7BitEncodedInt` does:
+    /// 7 bits per byte, least significant group first, high bit set if another byte follows
+    fn read_7bit_encoded_int(&mut self) -> error::Result<i32> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.read_u8()?;
+            // The 5th byte only has room for the 4 remaining bits
+            if shift == 28 && byte > 0b0000_1111 {
+                return Err(ProtocolError::TooLarge("7-bit encoded int"));
+            }
+            value |= u32::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(value as i32)
+    }
+
+    /// Reads a .NET `BinaryWriter` string: a 7-bit encoded byte length followed by UTF-8
+    fn read_terraria_string(&mut self) -> error::Result<String> {
+        let len = self.read_7bit_encoded_int()?;
+        let Ok(len) = usize::try_from(len) else {
+            return Err(ProtocolError::InvalidString);
+        };
+        if len > MAX_STRING_LEN {
+            return Err(ProtocolError::TooLarge("String"));
+        }
+        let mut buf = vec![0u8; len];
         self.read_exact(&mut buf)?;
-        Ok(String::from_utf8_lossy(&buf).to_string())
+        String::from_utf8(buf).map_err(|_| ProtocolError::InvalidString)
+    }
+
+    /// Reads a mode byte, a string and, unless the text is literal, the substitutions
+    fn read_network_text(&mut self) -> error::Result<NetworkText> {
+        read_network_text(self, 0)
     }
 
-    fn read_terraria_rgb(&mut self) -> io::Result<Rgb> {
+    fn read_terraria_rgb(&mut self) -> error::Result<Rgb> {
         let r = self.read_u8()?;
         let g = self.read_u8()?;
         let b = self.read_u8()?;
         Ok((r, g, b))
     }
+
+    fn read_terraria_vector2(&mut self) -> error::Result<Vector2> {
+        let x = self.read_f32::<LittleEndian>()?;
+        let y = self.read_f32::<LittleEndian>()?;
+        Ok((x, y))
+    }
 }
 
 impl<T: Write> TerrariaTypesW for T {
-    fn write_terraria_string(&mut self, value: String) -> io::Result<()> {
+    /// Writes an int the way .NET's `BinaryWriter.Write7BitEncodedInt` does
+    fn write_7bit_encoded_int(&mut self, value: i32) -> error::Result<()> {
         let mut bytes = vec![];
-        let mut len = value.len() as i32;
-        while len >= 128 {
-            bytes.push((len | 0x80) as u8);
-            len >>= 7;
+        let mut value = value as u32;
+        while value >= 0x80 {
+            bytes.push((value | 0x80) as u8);
+            value >>= 7;
+        }
+        bytes.push(value as u8);
+        Ok(self.write_all(&bytes)?)
+    }
+
+    /// Writes a .NET `BinaryWriter` string: a 7-bit encoded byte length followed by UTF-8
+    fn write_terraria_string(&mut self, value: String) -> error::Result<()> {
+        if value.len() > MAX_STRING_LEN {
+            return Err(ProtocolError::TooLarge("String"));
         }
-        bytes.push(len as u8);
-        bytes.extend(value.as_bytes());
-        self.write_all(&bytes)
+        self.write_7bit_encoded_int(value.len() as i32)?;
+        Ok(self.write_all(value.as_bytes())?)
     }
 
-    fn write_terraria_rgb(&mut self, value: Rgb) -> io::Result<()> {
+    fn write_network_text(&mut self, value: NetworkText) -> error::Result<()> {
+        self.write_u8(value.mode())?;
+        match value {
+            NetworkText::Literal(text) => self.write_terraria_string(text),
+            NetworkText::Formattable(text, substitutions)
+            | NetworkText::LocalizationKey(text, substitutions) => {
+                let Ok(len) = u8::try_from(substitutions.len()) else {
+                    return Err(ProtocolError::TooLarge("NetworkText substitutions"));
+                };
+                self.write_terraria_string(text)?;
+                self.write_u8(len)?;
+                for s in substitutions {
+                    self.write_network_text(s)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn write_terraria_rgb(&mut self, value: Rgb) -> error::Result<()> {
         let bytes = vec![value.0, value.1, value.2];
-        self.write_all(&bytes)
+        Ok(self.write_all(&bytes)?)
+    }
+
+    fn write_terraria_vector2(&mut self, value: Vector2) -> error::Result<()> {
+        self.write_f32::<LittleEndian>(value.0)?;
+        Ok(self.write_f32::<LittleEndian>(value.1)?)
+    }
+}
+
+fn read_network_text<T: Read>(r: &mut T, depth: usize) -> error::Result<NetworkText> {
+    if depth > MAX_DEPTH {
+        return Err(ProtocolError::TooLarge("NetworkText nesting"));
+    }
+    let mode = r.read_u8()?;
+    let text = r.read_terraria_string()?;
+    if mode == 0 {
+        return Ok(NetworkText::Literal(text));
+    }
+    let len = r.read_u8()?;
+    let mut substitutions = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        substitutions.push(read_network_text(r, depth + 1)?);
+    }
+    match mode {
+        1 => Ok(NetworkText::Formattable(text, substitutions)),
+        2 => Ok(NetworkText::LocalizationKey(text, substitutions)),
+        _ => Err(ProtocolError::invalid_enum_value("NetworkText mode", mode)),
     }
 }
@@ -0,0 +1,97 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A Terraria release and the protocol version its clients send in the connect request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameVersion {
+    pub release: &'static str,
+    pub protocol: u32,
+}
+
+const fn v(release: &'static str, protocol: u32) -> GameVersion {
+    GameVersion { release, protocol }
+}
+
+/// All 1.4 releases, oldest first
+pub const VERSIONS: &[GameVersion] = &[
+    v("1.4.0.1", 225),
+    v("1.4.0.2", 226),
+    v("1.4.0.3", 227),
+    v("1.4.0.4", 228),
+    v("1.4.0.5", 230),
+    v("1.4.1", 232),
+    v("1.4.1.1", 233),
+    v("1.4.1.2", 234),
+    v("1.4.2", 235),
+    v("1.4.2.1", 236),
+    v("1.4.2.2", 237),
+    v("1.4.2.3", 238),
+    v("1.4.3", 242),
+    v("1.4.3.1", 243),
+    v("1.4.3.2", 244),
+    v("1.4.3.3", 245),
+    v("1.4.3.4", 246),
+    v("1.4.3.5", 247),
+    v("1.4.3.6", 248),
+    v("1.4.4", 269),
+    v("1.4.4.1", 270),
+    v("1.4.4.2", 271),
+    v("1.4.4.3", 272),
+    v("1.4.4.4", 273),
+    v("1.4.4.5", 274),
+    v("1.4.4.6", 275),
+    v("1.4.4.7", 276),
+    v("1.4.4.8", 277),
+    v("1.4.4.8.1", 278),
+    v("1.4.4.9", 279),
+];
+
+/// The version the packets in this crate are written for
+pub const LATEST: GameVersion = v("1.4.4.9", 279);
+
+pub fn by_protocol(protocol: u32) -> Option<GameVersion> {
+    VERSIONS.iter().copied().find(|v| v.protocol == protocol)
+}
+
+pub fn by_release(release: &str) -> Option<GameVersion> {
+    VERSIONS.iter().copied().find(|v| v.release == release)
+}
+
+/// What the client identifies as in the connect request
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClientVersion {
+    /// A vanilla client with the given protocol version, sent as `Terraria279`
+    Vanilla(u32),
+    /// A tModLoader client with the given tModLoader version, sent as `tModLoader v2023.8.3.4`
+    TModLoader(String),
+}
+
+impl Default for ClientVersion {
+    fn default() -> Self {
+        Self::Vanilla(LATEST.protocol)
+    }
+}
+
+impl Display for ClientVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Vanilla(protocol) => write!(f, "Terraria{protocol}"),
+            Self::TModLoader(version) => write!(f, "tModLoader v{version}"),
+        }
+    }
+}
+
+impl FromStr for ClientVersion {
+    type Err = ();
+
+    /// Parses the version string of a connect request
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(version) = s.strip_prefix("tModLoader v") {
+            return Ok(Self::TModLoader(version.to_string()));
+        }
+        s.strip_prefix("Terraria")
+            .and_then(|protocol| protocol.parse().ok())
+            .map(Self::Vanilla)
+            .ok_or(())
+    }
+}
@@ -0,0 +1,321 @@
+//! Packets parsed straight out of a borrowed buffer.
+//!
+//! The owned packets in [`crate::packet`] copy every string and body into their own allocations.
+//! The views here point into the buffer the packet was received into instead, which is what the
+//! receive thread of a scan wants: most packets are looked at once and then thrown away.
+
+use crate::error;
+use crate::error::ProtocolError;
+use crate::network_text::{self, NetworkText, Parts, TextParts, MAX_DEPTH};
+use crate::packet::{
+    find_tmodloader_version, ModInfo, S2CConnectionApproved, S2CFatalError, S2CPasswordRequired,
+};
+use crate::types::{TerrariaTypesR, MAX_STRING_LEN};
+use byteorder::{LittleEndian, ReadBytesExt};
+
+/// A packet that can be parsed without copying out of the body it's in
+pub trait PacketView<'a>: Sized {
+    const PACKET_ID: u8;
+    /// Parses a packet body, without the length prefix and packet id
+    fn parse(body: &'a [u8]) -> error::Result<Self>;
+}
+
+/// Takes the next `len` bytes off the front of the slice
+fn take<'a>(r: &mut &'a [u8], len: usize) -> error::Result<&'a [u8]> {
+    if r.len() < len {
+        return Err(ProtocolError::Truncated);
+    }
+    let (head, tail) = r.split_at(len);
+    *r = tail;
+    Ok(head)
+}
+
+/// Reads a .NET `BinaryWriter` string without copying it
+fn read_str<'a>(r: &mut &'a [u8]) -> error::Result<&'a str> {
+    let Ok(len) = usize::try_from(r.read_7bit_encoded_int()?) else {
+        return Err(ProtocolError::InvalidString);
+    };
+    if len > MAX_STRING_LEN {
+        return Err(ProtocolError::TooLarge("String"));
+    }
+    std::str::from_utf8(take(r, len)?).map_err(|_| ProtocolError::InvalidString)
+}
+
+/// A [`NetworkText`] whose strings point into the packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkTextView<'a> {
+    Literal(&'a str),
+    Formattable(&'a str, Vec<NetworkTextView<'a>>),
+    LocalizationKey(&'a str, Vec<NetworkTextView<'a>>),
+}
+
+impl<'a> NetworkTextView<'a> {
+    fn read(r: &mut &'a [u8], depth: usize) -> error::Result<Self> {
+        if depth > MAX_DEPTH {
+            return Err(ProtocolError::TooLarge("NetworkText nesting"));
+        }
+        let mode = r.read_u8()?;
+        let text = read_str(r)?;
+        if mode == 0 {
+            return Ok(Self::Literal(text));
+        }
+        let len = r.read_u8()?;
+        let mut substitutions = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            substitutions.push(Self::read(r, depth + 1)?);
+        }
+        match mode {
+            1 => Ok(Self::Formattable(text, substitutions)),
+            2 => Ok(Self::LocalizationKey(text, substitutions)),
+            _ => Err(ProtocolError::invalid_enum_value("NetworkText mode", mode)),
+        }
+    }
+
+    /// The localization key, if this is a [`NetworkTextView::LocalizationKey`]
+    pub const fn key(&self) -> Option<&'a str> {
+        match self {
+            Self::LocalizationKey(key, _) => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Turns the text into English, like [`NetworkText::render`]
+    pub fn render(&self) -> String {
+        network_text::render(self)
+    }
+
+    /// Like [`NetworkText::mentions`]
+    pub fn mentions(&self, needle: &str) -> bool {
+        network_text::mentions(self, needle)
+    }
+
+    pub fn into_owned(self) -> NetworkText {
+        let owned = |substitutions: Vec<Self>| {
+            substitutions
+                .into_iter()
+                .map(Self::into_owned)
+                .collect::<Vec<NetworkText>>()
+        };
+        match self {
+            Self::Literal(text) => NetworkText::Literal(text.to_string()),
+            Self::Formattable(text, substitutions) => {
+                NetworkText::Formattable(text.to_string(), owned(substitutions))
+            }
+            Self::LocalizationKey(key, substitutions) => {
+                NetworkText::LocalizationKey(key.to_string(), owned(substitutions))
+            }
+        }
+    }
+}
+
+impl TextParts for NetworkTextView<'_> {
+    fn parts(&self) -> Parts<'_, Self> {
+        match self {
+            Self::Literal(text) => Parts::Literal(text),
+            Self::Formattable(format, substitutions) => Parts::Formattable(format, substitutions),
+            Self::LocalizationKey(key, substitutions) => Parts::LocalizationKey(key, substitutions),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct S2CFatalErrorView<'a> {
+    pub error: NetworkTextView<'a>,
+}
+
+impl S2CFatalErrorView<'_> {
+    /// Whether the server kicked us because we sent a different protocol version
+    pub fn is_version_mismatch(&self) -> bool {
+        self.error.key() == Some("LegacyMultiplayer.4")
+    }
+
+    /// Whether the kick reason comes from a tModLoader server, without rendering it
+    pub fn is_tmodloader(&self) -> bool {
+        self.error
+            .key()
+            .is_some_and(|k| k.starts_with("tModLoader."))
+            || self.error.mentions("tModLoader")
+    }
+
+    /// See [`find_tmodloader_version`]
+    pub fn tmodloader_version(&self) -> Option<String> {
+        find_tmodloader_version(&self.error.render())
+    }
+
+    pub fn into_owned(self) -> S2CFatalError {
+        S2CFatalError {
+            error: self.error.into_owned(),
+        }
+    }
+}
+
+impl<'a> PacketView<'a> for S2CFatalErrorView<'a> {
+    const PACKET_ID: u8 = 2;
+
+    fn parse(mut body: &'a [u8]) -> error::Result<Self> {
+        let error = NetworkTextView::read(&mut body, 0)?;
+        Ok(Self { error })
+    }
+}
+
+impl PacketView<'_> for S2CConnectionApproved {
+    const PACKET_ID: u8 = 3;
+
+    fn parse(mut body: &[u8]) -> error::Result<Self> {
+        let slot = body.read_u8()?;
+        let check_bytes = body.read_u8()? != 0;
+        Ok(Self { slot, check_bytes })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct S2CStatusTextView<'a> {
+    pub status_max: i32,
+    pub text: NetworkTextView<'a>,
+    pub flags: u8,
+}
+
+impl<'a> PacketView<'a> for S2CStatusTextView<'a> {
+    const PACKET_ID: u8 = 9;
+
+    fn parse(mut body: &'a [u8]) -> error::Result<Self> {
+        let status_max = body.read_i32::<LittleEndian>()?;
+        let text = NetworkTextView::read(&mut body, 0)?;
+        let flags = body.read_u8()?;
+        Ok(Self {
+            status_max,
+            text,
+            flags,
+        })
+    }
+}
+
+impl PacketView<'_> for S2CPasswordRequired {
+    const PACKET_ID: u8 = 37;
+
+    fn parse(_: &[u8]) -> error::Result<Self> {
+        Ok(Self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct S2CNetModuleView<'a> {
+    pub module_id: u16,
+    pub body: &'a [u8],
+}
+
+impl<'a> PacketView<'a> for S2CNetModuleView<'a> {
+    const PACKET_ID: u8 = 82;
+
+    fn parse(mut body: &'a [u8]) -> error::Result<Self> {
+        let module_id = body.read_u16::<LittleEndian>()?;
+        Ok(Self { module_id, body })
+    }
+}
+
+/// A [`ModInfo`] whose strings point into the packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModInfoView<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    pub hash: &'a [u8; 20],
+    pub signed: bool,
+    pub configs: Vec<(&'a str, &'a str)>,
+}
+
+impl ModInfoView<'_> {
+    pub fn into_owned(self) -> ModInfo {
+        ModInfo {
+            name: self.name.to_string(),
+            version: self.version.to_string(),
+            hash: *self.hash,
+            signed: self.signed,
+            configs: self
+                .configs
+                .into_iter()
+                .map(|(name, json)| (name.to_string(), json.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// Only sent by tModLoader servers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct S2CSyncModsView<'a> {
+    pub allow_vanilla_clients: bool,
+    pub mods: Vec<ModInfoView<'a>>,
+}
+
+impl<'a> PacketView<'a> for S2CSyncModsView<'a> {
+    const PACKET_ID: u8 = 251;
+
+    fn parse(mut body: &'a [u8]) -> error::Result<Self> {
+        let allow_vanilla_clients = body.read_u8()? != 0;
+        let mod_count = body.read_i32::<LittleEndian>()?;
+        let mut mods = vec![];
+        for _ in 0..mod_count {
+            let name = read_str(&mut body)?;
+            let version = read_str(&mut body)?;
+            let hash = take(&mut body, 20)?
+                .try_into()
+                .map_err(|_| ProtocolError::Truncated)?;
+            let signed = body.read_u8()? != 0;
+            let config_count = body.read_i32::<LittleEndian>()?;
+            let mut configs = vec![];
+            for _ in 0..config_count {
+                configs.push((read_str(&mut body)?, read_str(&mut body)?));
+            }
+            mods.push(ModInfoView {
+                name,
+                version,
+                hash,
+                signed,
+                configs,
+            });
+        }
+        Ok(Self {
+            allow_vanilla_clients,
+            mods,
+        })
+    }
+}
+
+/// The packets a server answers a connection request with, parsed without copying.
+/// Everything else is left as the raw body
+pub enum S2CPacketView<'a> {
+    FatalError(S2CFatalErrorView<'a>),
+    ConnectionApproved(S2CConnectionApproved),
+    StatusText(S2CStatusTextView<'a>),
+    PasswordRequired(S2CPasswordRequired),
+    NetModule(S2CNetModuleView<'a>),
+    SyncMods(S2CSyncModsView<'a>),
+    Other { id: u8, body: &'a [u8] },
+}
+
+impl<'a> S2CPacketView<'a> {
+    /// Parses a packet body based on the packet id
+    pub fn parse(id: u8, body: &'a [u8]) -> error::Result<Self> {
+        Ok(match id {
+            2 => Self::FatalError(S2CFatalErrorView::parse(body)?),
+            3 => Self::ConnectionApproved(S2CConnectionApproved::parse(body)?),
+            9 => Self::StatusText(S2CStatusTextView::parse(body)?),
+            37 => Self::PasswordRequired(S2CPasswordRequired::parse(body)?),
+            82 => Self::NetModule(S2CNetModuleView::parse(body)?),
+            251 => Self::SyncMods(S2CSyncModsView::parse(body)?),
+            id => Self::Other { id, body },
+        })
+    }
+
+    /// The packet id of this packet
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::FatalError(_) => 2,
+            Self::ConnectionApproved(_) => 3,
+            Self::StatusText(_) => 9,
+            Self::PasswordRequired(_) => 37,
+            Self::NetModule(_) => 82,
+            Self::SyncMods(_) => 251,
+            Self::Other { id, .. } => *id,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "tokio")]
+
+use chlorophyte_terraria_protocol::codec::TerrariaConnection;
+use chlorophyte_terraria_protocol::error::ProtocolError;
+use chlorophyte_terraria_protocol::packet::{C2SConnect, S2CPacket};
+use chlorophyte_terraria_protocol::version::ClientVersion;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn connect_and_get_approved() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut connect = [0u8; 15];
+        stream.read_exact(&mut connect).await.unwrap();
+        // Connection approved for slot 5, split in the middle of the length prefix
+        stream.write_all(&[5]).await.unwrap();
+        stream.flush().await.unwrap();
+        stream.write_all(&[0, 3, 5, 0]).await.unwrap();
+        connect
+    });
+
+    let mut connection = TerrariaConnection::connect(addr).await.unwrap();
+    connection
+        .send(C2SConnect {
+            version: ClientVersion::Vanilla(279),
+        })
+        .await
+        .unwrap();
+    let packet = connection.next_packet().await.unwrap();
+    assert!(matches!(packet, Some(S2CPacket::ConnectionApproved(p)) if p.slot == 5));
+    assert!(connection.next_packet().await.unwrap().is_none());
+
+    let connect = server.await.unwrap();
+    assert_eq!(connect[..4], [15, 0, 1, 11]);
+    assert_eq!(&connect[4..], b"Terraria279");
+}
+
+#[tokio::test]
+async fn invalid_length_prefix() {
+    let (client, mut server) = tokio::io::duplex(64);
+    server.write_all(&[1, 0, 3]).await.unwrap();
+    let mut connection = TerrariaConnection::new(client);
+    assert!(matches!(
+        connection.next_packet().await,
+        Err(ProtocolError::LengthMismatch(_))
+    ));
+}
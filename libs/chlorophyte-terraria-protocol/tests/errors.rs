@@ -0,0 +1,61 @@
+use chlorophyte_terraria_protocol::error::ProtocolError;
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::packet::{
+    C2SSetBuffs, ReadTerrariaPacket, S2CConnectionApproved, S2CPacket, TerrariaPacket,
+};
+use std::io::Cursor;
+
+#[test]
+fn truncated_body() {
+    // A world info packet that ends after the time
+    let frame = [7, 0, 7, 1, 2, 3, 4];
+    let result = Cursor::new(frame.to_vec()).read_any_packet();
+    assert!(matches!(result, Err(ProtocolError::Truncated)));
+}
+
+#[test]
+fn truncated_frame() {
+    let result = Cursor::new(vec![10, 0, 3, 0]).read_any_packet();
+    assert!(matches!(result, Err(ProtocolError::Truncated)));
+}
+
+#[test]
+fn length_prefix_smaller_than_header() {
+    let mut decoder = TerrariaFrameDecoder::new();
+    decoder.feed(&[2, 0, 3]);
+    assert!(matches!(
+        decoder.next_packet(),
+        Err(ProtocolError::LengthMismatch(_))
+    ));
+}
+
+#[test]
+fn unexpected_packet_id() {
+    let result = Cursor::new(vec![5, 0, 2, 0, 0]).read_terraria_packet::<S2CConnectionApproved>();
+    assert!(matches!(result, Err(ProtocolError::UnknownPacketId(2))));
+}
+
+#[test]
+fn invalid_string() {
+    // A fatal error with a literal text of two bytes that aren't UTF-8
+    let result = S2CPacket::deserialize(2, vec![0, 2, 0xC3, 0x28]);
+    assert!(matches!(result, Err(ProtocolError::InvalidString)));
+}
+
+#[test]
+fn invalid_network_text_mode() {
+    let result = S2CPacket::deserialize(2, vec![7, 0, 0]);
+    assert!(matches!(
+        result,
+        Err(ProtocolError::InvalidEnumValue { value: 7, .. })
+    ));
+}
+
+#[test]
+fn too_many_buffs() {
+    let buffs = C2SSetBuffs {
+        slot: 0,
+        buffs: vec![1; 45],
+    };
+    assert!(matches!(buffs.serialize(), Err(ProtocolError::TooLarge(_))));
+}
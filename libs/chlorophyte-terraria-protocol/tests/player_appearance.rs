@@ -0,0 +1,71 @@
+use chlorophyte_terraria_protocol::packet::{
+    C2SPlayerAppearance, Difficulty, S2CPlayerInfo, TerrariaPacket,
+};
+
+fn appearance() -> C2SPlayerAppearance {
+    let mut hide_visible_accessory = [false; 10];
+    hide_visible_accessory[1] = true;
+    hide_visible_accessory[9] = true;
+    C2SPlayerAppearance {
+        slot: 2,
+        skin_variant: 4,
+        hair: 17,
+        name: "Chlorophyte".to_string(),
+        hair_dye: 0,
+        hide_visible_accessory,
+        hide_misc: false,
+        hair_color: (215, 90, 55),
+        skin_color: (255, 125, 90),
+        eye_color: (105, 90, 75),
+        shirt_color: (175, 165, 140),
+        undershirt_color: (160, 180, 215),
+        pants_color: (255, 230, 175),
+        shoe_color: (160, 105, 60),
+        difficulty: Difficulty::Journey,
+        extra_accessory: true,
+        using_biome_torches: true,
+        happy_fun_torch_time: false,
+        unlocked_biome_torches: false,
+        unlocked_super_cart: false,
+        enabled_super_cart: true,
+        used_aegis_crystal: false,
+        used_aegis_fruit: false,
+        used_arcane_crystal: false,
+        used_galaxy_pearl: false,
+        used_gummy_worm: false,
+        used_ambrosia: false,
+        ate_artisan_bread: true,
+    }
+}
+
+#[test]
+fn bits_are_least_significant_first() {
+    let body = appearance().serialize().unwrap();
+    let name_end = 3 + 1 + "Chlorophyte".len();
+    // Hair dye, then the hidden accessories as two bytes
+    assert_eq!(body[name_end + 1..name_end + 3], [0b0000_0010, 0b0000_0010]);
+    assert_eq!(
+        body[body.len() - 3..],
+        [0b0000_1100, 0b0001_0001, 0b0100_0000]
+    );
+}
+
+#[test]
+fn round_trip() {
+    let body = appearance().serialize().unwrap();
+    let decoded = C2SPlayerAppearance::deserialize(body.clone()).unwrap();
+    assert!(decoded.using_biome_torches && !decoded.unlocked_biome_torches);
+    assert_eq!(decoded.serialize().unwrap(), body);
+}
+
+#[test]
+fn server_reads_the_same_layout() {
+    let info = S2CPlayerInfo::deserialize(appearance().serialize().unwrap()).unwrap();
+    assert_eq!(info.slot, 2);
+    assert_eq!(info.name, "Chlorophyte");
+    assert_eq!(info.difficulty, Difficulty::Journey);
+    assert!(info.extra_accessory);
+    assert!(info.hide_visible_accessory[1] && info.hide_visible_accessory[9]);
+    assert_eq!(info.torch_flags, 0b0001_0001);
+    assert_eq!(info.upgrade_flags, 0b0100_0000);
+}
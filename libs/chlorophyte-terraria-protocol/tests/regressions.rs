@@ -0,0 +1,172 @@
+//! Inputs that used to panic, hang or allocate without bound, kept so they stay fixed
+
+use chlorophyte_terraria_protocol::error::ProtocolError;
+use chlorophyte_terraria_protocol::net_module::NetModule;
+use chlorophyte_terraria_protocol::network_text::{NetworkText, MAX_DEPTH};
+use chlorophyte_terraria_protocol::packet::{S2CFatalError, S2CPacket, S2CSendSection};
+use chlorophyte_terraria_protocol::tile::TileGrid;
+use chlorophyte_terraria_protocol::types::{TerrariaTypesR, TerrariaTypesW, MAX_STRING_LEN};
+use std::time::{Duration, Instant};
+
+#[test]
+fn string_length_below_two() {
+    // The length used to be read as `read_u8()? - 2`, which overflowed for 0 and 1
+    assert_eq!((&mut &[0u8][..]).read_terraria_string().unwrap(), "");
+    assert_eq!((&mut &[1, b'a'][..]).read_terraria_string().unwrap(), "a");
+}
+
+#[test]
+fn string_with_negative_length() {
+    let len = [0xFF, 0xFF, 0xFF, 0xFF, 0x0F];
+    assert!(matches!(
+        (&mut &len[..]).read_terraria_string(),
+        Err(ProtocolError::InvalidString)
+    ));
+}
+
+#[test]
+fn string_longer_than_any_packet() {
+    // 16 MiB, which would be allocated before noticing the body is empty
+    let len = [0x80, 0x80, 0x80, 0x08];
+    assert!(matches!(
+        (&mut &len[..]).read_terraria_string(),
+        Err(ProtocolError::TooLarge(_))
+    ));
+}
+
+#[test]
+fn seven_bit_int_that_never_ends() {
+    let len = [0xFF; 6];
+    assert!(matches!(
+        (&mut &len[..]).read_7bit_encoded_int(),
+        Err(ProtocolError::TooLarge(_))
+    ));
+}
+
+#[test]
+fn deeply_nested_network_text() {
+    // Formattable texts with one substitution each, nested until the stack overflows
+    let mut body = vec![];
+    for _ in 0..=MAX_DEPTH * 1000 {
+        body.extend_from_slice(&[1, 0, 1]);
+    }
+    assert!(matches!(
+        S2CPacket::deserialize(2, body),
+        Err(ProtocolError::TooLarge(_))
+    ));
+}
+
+#[test]
+fn section_larger_than_a_world() {
+    // 32767 x 32767 tiles starting at 0, 0
+    let mut body = vec![0; 8];
+    body.extend_from_slice(&[0xFF, 0x7F, 0xFF, 0x7F]);
+    assert!(matches!(
+        TileGrid::read_section(&mut body.as_slice()),
+        Err(ProtocolError::TooLarge(_))
+    ));
+}
+
+#[test]
+fn section_larger_than_vanilla_sends() {
+    // 2048 x 2048 tiles, 16 of them would fill the whole grid
+    let mut body = vec![0; 8];
+    body.extend_from_slice(&[0, 8, 0, 8]);
+    let section = S2CSendSection {
+        compressed: false,
+        data: body,
+    };
+    assert!(matches!(section.bounds(), Err(ProtocolError::TooLarge(_))));
+    assert!(matches!(section.tiles(), Err(ProtocolError::TooLarge(_))));
+}
+
+#[test]
+fn section_past_the_end_of_i32_coordinates() {
+    let mut body = i32::MAX.to_le_bytes().to_vec();
+    body.extend_from_slice(&[0, 0, 0, 0, 2, 0, 1, 0]);
+    let section = S2CSendSection {
+        compressed: false,
+        data: body,
+    };
+    assert!(matches!(section.tiles(), Err(ProtocolError::TooLarge(_))));
+}
+
+#[test]
+fn liquid_count_larger_than_the_body() {
+    // 65535 liquid changes announced, none sent
+    assert!(matches!(
+        NetModule::deserialize(0, &[0xFF, 0xFF]),
+        Err(ProtocolError::Truncated)
+    ));
+}
+
+#[test]
+fn network_text_that_multiplies_when_rendered() {
+    // Every level repeats the one below 300 times, 270 MB after three levels and far more after five
+    let mut text = NetworkText::Literal("x".repeat(100));
+    for _ in 0..5 {
+        text = NetworkText::Formattable("{0}".repeat(300), vec![text]);
+    }
+    assert_eq!(text.render().len(), MAX_STRING_LEN);
+}
+
+#[test]
+fn network_text_that_renders_to_nothing() {
+    // 54 KB, but 216 billion substitutions when every one of them is rendered
+    let mut text = NetworkText::Literal(String::new());
+    for _ in 0..3 {
+        text = NetworkText::Formattable("{0}".repeat(6000), vec![text]);
+    }
+    let mut body = vec![];
+    body.write_network_text(text).unwrap();
+    let S2CPacket::FatalError(kick) = S2CPacket::deserialize(2, body).unwrap() else {
+        panic!("Expected a fatal error");
+    };
+    let start = Instant::now();
+    assert_eq!(kick.error.render(), "");
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn network_text_substitution_is_not_substituted_again() {
+    let text = NetworkText::Formattable(
+        "{0} and {1}".to_string(),
+        vec![
+            NetworkText::Literal("{1}".to_string()),
+            NetworkText::Literal("b".to_string()),
+        ],
+    );
+    assert_eq!(text.render(), "{1} and b");
+}
+
+#[test]
+fn tmodloader_version_longer_than_a_packet() {
+    // The version is sent back in a connection request, which this would no longer fit into
+    let kick = |version: String| S2CFatalError {
+        error: NetworkText::Formattable(
+            "tModLoader v{0}".to_string(),
+            vec![NetworkText::Literal(version)],
+        ),
+    };
+    assert_eq!(kick("1".repeat(70_000)).tmodloader_version(), None);
+    assert_eq!(kick("1.2.3.4.5".to_string()).tmodloader_version(), None);
+    assert_eq!(kick("1..2".to_string()).tmodloader_version(), None);
+    assert_eq!(
+        kick("2023.8.3.4.".to_string())
+            .tmodloader_version()
+            .as_deref(),
+        Some("2023.8.3.4")
+    );
+}
+
+#[test]
+fn tmodloader_kick_is_detected_without_rendering() {
+    let mut text = NetworkText::Literal("tModLoader".to_string());
+    for _ in 0..3 {
+        text = NetworkText::Formattable("{0}".repeat(6000), vec![text]);
+    }
+    let kick = S2CFatalError { error: text };
+    let start = Instant::now();
+    assert!(kick.is_tmodloader());
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
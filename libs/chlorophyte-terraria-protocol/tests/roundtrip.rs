@@ -0,0 +1,426 @@
+//! Every packet has to decode to exactly what was encoded
+
+use chlorophyte_terraria_protocol::network_text::NetworkText;
+use chlorophyte_terraria_protocol::packet::*;
+use chlorophyte_terraria_protocol::version::ClientVersion;
+use proptest::prelude::*;
+use std::fmt::Debug;
+use std::io::Cursor;
+
+fn round_trip<P: TerrariaPacket + Clone + Debug + PartialEq>(
+    packet: P,
+) -> Result<(), TestCaseError> {
+    let mut frame = vec![];
+    frame.write_terraria_packet(packet.clone()).unwrap();
+    let decoded = Cursor::new(frame).read_terraria_packet::<P>().unwrap();
+    prop_assert_eq!(decoded, packet);
+    Ok(())
+}
+
+fn string() -> impl Strategy<Value = String> {
+    "\\PC{0,24}"
+}
+
+fn float() -> impl Strategy<Value = f32> {
+    prop::num::f32::NORMAL | prop::num::f32::SUBNORMAL | prop::num::f32::ZERO
+}
+
+fn vector2() -> impl Strategy<Value = (f32, f32)> {
+    (float(), float())
+}
+
+fn network_text() -> impl Strategy<Value = NetworkText> {
+    string()
+        .prop_map(NetworkText::Literal)
+        .prop_recursive(3, 16, 4, |inner| {
+            prop_oneof![
+                (string(), prop::collection::vec(inner.clone(), 0..4))
+                    .prop_map(|(text, s)| NetworkText::Formattable(text, s)),
+                (string(), prop::collection::vec(inner, 0..4))
+                    .prop_map(|(key, s)| NetworkText::LocalizationKey(key, s)),
+            ]
+        })
+}
+
+fn difficulty() -> impl Strategy<Value = Difficulty> {
+    prop_oneof![
+        Just(Difficulty::Normal),
+        Just(Difficulty::Mediumcore),
+        Just(Difficulty::Hardcore),
+        Just(Difficulty::Journey),
+    ]
+}
+
+fn client_version() -> impl Strategy<Value = ClientVersion> {
+    prop_oneof![
+        any::<u32>().prop_map(ClientVersion::Vanilla),
+        "[0-9]{4}(\\.[0-9]{1,2}){3}".prop_map(ClientVersion::TModLoader),
+    ]
+}
+
+fn buffs() -> impl Strategy<Value = Vec<u16>> {
+    // Empty buff slots are sent as zeroes, the ones at the end are left out when decoding
+    let slot = prop_oneof![Just(0), 1..=u16::MAX];
+    prop::collection::vec(slot, 0..=MAX_BUFFS).prop_map(|mut buffs| {
+        while buffs.last() == Some(&0) {
+            buffs.pop();
+        }
+        buffs
+    })
+}
+
+prop_compose! {
+    fn player_info()(
+        (slot, skin_variant, hair, name, hair_dye) in
+            (any::<u8>(), any::<u8>(), any::<u8>(), string(), any::<u8>()),
+        (hide_visible_accessory, hide_misc) in (any::<[bool; 10]>(), any::<u8>()),
+        colors in any::<[(u8, u8, u8); 7]>(),
+        (difficulty, extra_accessory) in (difficulty(), any::<bool>()),
+        (torch_flags, upgrade_flags) in (any::<u8>(), any::<u8>()),
+    ) -> S2CPlayerInfo {
+        let [hair_color, skin_color, eye_color, shirt_color, undershirt_color, pants_color, shoe_color] = colors;
+        S2CPlayerInfo {
+            slot,
+            skin_variant,
+            hair,
+            name,
+            hair_dye,
+            hide_visible_accessory,
+            hide_misc,
+            hair_color,
+            skin_color,
+            eye_color,
+            shirt_color,
+            undershirt_color,
+            pants_color,
+            shoe_color,
+            difficulty,
+            extra_accessory,
+            torch_flags,
+            upgrade_flags,
+        }
+    }
+}
+
+prop_compose! {
+    fn player_appearance()(
+        info in player_info(),
+        hide_misc in any::<bool>(),
+        torches in any::<[bool; 5]>(),
+        upgrades in any::<[bool; 7]>(),
+    ) -> C2SPlayerAppearance {
+        C2SPlayerAppearance {
+            slot: info.slot,
+            skin_variant: info.skin_variant,
+            hair: info.hair,
+            name: info.name,
+            hair_dye: info.hair_dye,
+            hide_visible_accessory: info.hide_visible_accessory,
+            hide_misc,
+            hair_color: info.hair_color,
+            skin_color: info.skin_color,
+            eye_color: info.eye_color,
+            shirt_color: info.shirt_color,
+            undershirt_color: info.undershirt_color,
+            pants_color: info.pants_color,
+            shoe_color: info.shoe_color,
+            difficulty: info.difficulty,
+            extra_accessory: info.extra_accessory,
+            using_biome_torches: torches[0],
+            happy_fun_torch_time: torches[1],
+            unlocked_biome_torches: torches[2],
+            unlocked_super_cart: torches[3],
+            enabled_super_cart: torches[4],
+            used_aegis_crystal: upgrades[0],
+            used_aegis_fruit: upgrades[1],
+            used_arcane_crystal: upgrades[2],
+            used_galaxy_pearl: upgrades[3],
+            used_gummy_worm: upgrades[4],
+            used_ambrosia: upgrades[5],
+            ate_artisan_bread: upgrades[6],
+        }
+    }
+}
+
+prop_compose! {
+    fn world_info()(
+        (time, day_time, blood_moon, eclipse, moon_phase) in
+            (any::<i32>(), any::<bool>(), any::<bool>(), any::<bool>(), any::<u8>()),
+        layout in any::<[i16; 6]>(),
+        (world_id, world_name, game_mode, unique_id, world_generator_version) in
+            (any::<i32>(), string(), any::<u8>(), any::<[u8; 16]>(), any::<u64>()),
+        (moon_type, backgrounds, back_styles) in
+            (any::<u8>(), any::<[u8; 13]>(), any::<[u8; 3]>()),
+        (wind_speed_target, num_clouds, tree_x, tree_style) in
+            (float(), any::<u8>(), any::<[i32; 3]>(), any::<[u8; 4]>()),
+        (cave_back_x, cave_back_style, tree_tops, max_raining) in
+            (any::<[i32; 3]>(), any::<[u8; 4]>(), any::<[u8; 13]>(), float()),
+        (flags, sundial_cooldown, moondial_cooldown, ore_tiers) in
+            (any::<[u8; 10]>(), any::<u8>(), any::<u8>(), any::<[i16; 7]>()),
+        (invasion_type, lobby_id, sandstorm_severity) in (any::<i8>(), any::<u64>(), float()),
+    ) -> S2CWorldInfo {
+        let [max_tiles_x, max_tiles_y, spawn_x, spawn_y, world_surface, rock_layer] = layout;
+        let [ice_back_style, jungle_back_style, hell_back_style] = back_styles;
+        S2CWorldInfo {
+            time,
+            day_time,
+            blood_moon,
+            eclipse,
+            moon_phase,
+            max_tiles_x,
+            max_tiles_y,
+            spawn_x,
+            spawn_y,
+            world_surface,
+            rock_layer,
+            world_id,
+            world_name,
+            game_mode,
+            unique_id,
+            world_generator_version,
+            moon_type,
+            backgrounds,
+            ice_back_style,
+            jungle_back_style,
+            hell_back_style,
+            wind_speed_target,
+            num_clouds,
+            tree_x,
+            tree_style,
+            cave_back_x,
+            cave_back_style,
+            tree_tops,
+            max_raining,
+            flags: WorldFlags::from_bytes(flags),
+            sundial_cooldown,
+            moondial_cooldown,
+            ore_tiers,
+            invasion_type,
+            lobby_id,
+            sandstorm_severity,
+        }
+    }
+}
+
+prop_compose! {
+    fn sync_item()(
+        (item_id, position, velocity, stack) in (any::<i16>(), vector2(), vector2(), any::<i16>()),
+        (prefix_id, no_delay, net_id) in (any::<u8>(), any::<u8>(), any::<i16>()),
+    ) -> S2CSyncItem {
+        S2CSyncItem { item_id, position, velocity, stack, prefix_id, no_delay, net_id }
+    }
+}
+
+prop_compose! {
+    fn mod_info()(
+        (name, version, hash, signed) in (string(), string(), any::<[u8; 20]>(), any::<bool>()),
+        configs in prop::collection::vec((string(), string()), 0..4),
+    ) -> ModInfo {
+        ModInfo { name, version, hash, signed, configs }
+    }
+}
+
+proptest! {
+    #[test]
+    fn connect(version in client_version()) {
+        round_trip(C2SConnect { version })?;
+    }
+
+    #[test]
+    fn fatal_error(error in network_text()) {
+        round_trip(S2CFatalError { error })?;
+    }
+
+    #[test]
+    fn connection_approved(slot in any::<u8>(), check_bytes in any::<bool>()) {
+        round_trip(S2CConnectionApproved { slot, check_bytes })?;
+    }
+
+    #[test]
+    fn player_info_round_trip(packet in player_info()) {
+        round_trip(packet)?;
+    }
+
+    #[test]
+    fn player_appearance_round_trip(packet in player_appearance()) {
+        round_trip(packet)?;
+    }
+
+    #[test]
+    fn world_info_round_trip(packet in world_info()) {
+        round_trip(packet)?;
+    }
+
+    #[test]
+    fn status_text(status_max in any::<i32>(), text in network_text(), flags in any::<u8>()) {
+        round_trip(S2CStatusText { status_max, text, flags })?;
+    }
+
+    #[test]
+    fn send_section(compressed in any::<bool>(), data in prop::collection::vec(any::<u8>(), 0..512)) {
+        round_trip(S2CSendSection { compressed, data })?;
+    }
+
+    #[test]
+    fn tile_frame_section(coordinates in any::<[i16; 4]>()) {
+        let [start_x, start_y, end_x, end_y] = coordinates;
+        round_trip(S2CTileFrameSection { start_x, start_y, end_x, end_y })?;
+    }
+
+    #[test]
+    fn player_spawn(
+        (slot, spawn_x, spawn_y, respawn_timer) in (any::<u8>(), any::<i16>(), any::<i16>(), any::<i32>()),
+        (deaths_pve, deaths_pvp, context) in (any::<i16>(), any::<i16>(), any::<u8>()),
+    ) {
+        round_trip(S2CPlayerSpawn { slot, spawn_x, spawn_y, respawn_timer, deaths_pve, deaths_pvp, context })?;
+        round_trip(C2SSpawnPlayer { slot, spawn_x, spawn_y, respawn_timer, deaths_pve, deaths_pvp, context })?;
+    }
+
+    #[test]
+    fn player_active(slot in any::<u8>(), active in any::<bool>()) {
+        round_trip(S2CPlayerActive { slot, active })?;
+    }
+
+    #[test]
+    fn player_health(slot in any::<u8>(), stat_life in any::<i16>(), stat_life_max in any::<i16>()) {
+        round_trip(S2CPlayerHealth { slot, stat_life, stat_life_max })?;
+        round_trip(C2SSetHealth { slot, stat_life, stat_life_max })?;
+    }
+
+    #[test]
+    fn sync_item_round_trip(packet in sync_item()) {
+        round_trip(packet)?;
+    }
+
+    #[test]
+    fn player_mana(slot in any::<u8>(), stat_mana in any::<i16>(), stat_mana_max in any::<i16>()) {
+        round_trip(S2CPlayerMana { slot, stat_mana, stat_mana_max })?;
+        round_trip(C2SSetMana { slot, stat_mana, stat_mana_max })?;
+    }
+
+    #[test]
+    fn player_buffs(slot in any::<u8>(), buffs in buffs()) {
+        round_trip(S2CPlayerBuffs { slot, buffs: buffs.clone() })?;
+        round_trip(C2SSetBuffs { slot, buffs })?;
+    }
+
+    #[test]
+    fn tile_counts(good in any::<u8>(), evil in any::<u8>(), blood in any::<u8>()) {
+        round_trip(S2CTileCounts { good, evil, blood })?;
+    }
+
+    #[test]
+    fn angler_quest(quest in any::<u8>(), completed in any::<bool>()) {
+        round_trip(S2CAnglerQuest { quest, completed })?;
+    }
+
+    #[test]
+    fn net_module(module_id in any::<u16>(), body in prop::collection::vec(any::<u8>(), 0..512)) {
+        round_trip(S2CNetModule { module_id, body })?;
+    }
+
+    #[test]
+    fn kill_count(npc_type in any::<i16>(), count in any::<i32>()) {
+        round_trip(S2CKillCount { npc_type, count })?;
+    }
+
+    #[test]
+    fn tower_shields(shields in any::<[u16; 4]>()) {
+        let [solar, vortex, nebula, stardust] = shields;
+        round_trip(S2CTowerShields { solar, vortex, nebula, stardust })?;
+    }
+
+    #[test]
+    fn moon_lord_countdown(countdown in any::<i32>(), max_countdown in any::<i32>()) {
+        round_trip(S2CMoonLordCountdown { countdown, max_countdown })?;
+    }
+
+    #[test]
+    fn set_counts_as_host(slot in any::<u8>(), counts_as_host in any::<bool>()) {
+        round_trip(S2CSetCountsAsHost { slot, counts_as_host })?;
+    }
+
+    #[test]
+    fn sync_mods(allow_vanilla_clients in any::<bool>(), mods in prop::collection::vec(mod_info(), 0..4)) {
+        round_trip(S2CSyncMods { allow_vanilla_clients, mods })?;
+    }
+
+    #[test]
+    fn set_inv_slot(
+        (slot, inv_slot, stack, prefix_id, net_id) in
+            (any::<u8>(), any::<u8>(), any::<i16>(), any::<u8>(), any::<i16>()),
+    ) {
+        round_trip(C2SSetInvSlot { slot, inv_slot, stack, prefix_id, net_id })?;
+    }
+
+    #[test]
+    fn request_initial_tile_data(x in any::<i32>(), y in any::<i32>()) {
+        round_trip(C2SRequestInitialTileData { x, y })?;
+    }
+
+    #[test]
+    fn client_uuid(uuid in string()) {
+        round_trip(C2SClientUuid { uuid })?;
+    }
+
+    #[test]
+    fn send_password(password in string()) {
+        round_trip(C2SSendPassword { password })?;
+    }
+
+    #[test]
+    fn net_message_command(command in string(), text in string()) {
+        round_trip(C2SNetMessageCommand { command, text })?;
+    }
+}
+
+#[test]
+fn packets_without_fields() {
+    round_trip(S2CPasswordRequired).unwrap();
+    round_trip(S2CCompleteConnectionAndSpawn).unwrap();
+    round_trip(S2CFinishedConnectingToServer).unwrap();
+    round_trip(C2SRequestWorldInfo).unwrap();
+}
+
+#[test]
+fn buffs_after_an_empty_slot() {
+    let buffs = S2CPlayerBuffs {
+        slot: 3,
+        buffs: vec![1, 0, 0, 26],
+    };
+    round_trip(buffs.clone()).unwrap();
+    // The slot and all 44 buff slots, like the game sends it
+    let body = buffs.serialize().unwrap();
+    assert_eq!(body.len(), 1 + MAX_BUFFS * 2);
+    assert_eq!(body[7..9], [26, 0]);
+}
+
+/// No packet we know of sends a 7-bit encoded int outside of strings, so this one is made up
+#[derive(Clone, Debug, PartialEq, TerrariaPacket)]
+#[packet(id = 250, direction = ClientToServer)]
+struct VarintPacket {
+    before: u8,
+    #[packet(varint)]
+    value: i32,
+    after: u8,
+}
+
+#[test]
+fn varint_field() {
+    for (value, encoded) in [
+        (0, &[0x00][..]),
+        (300, &[0xAC, 0x02]),
+        (-1, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+    ] {
+        let packet = VarintPacket {
+            before: 7,
+            value,
+            after: 9,
+        };
+        round_trip(packet.clone()).unwrap();
+        let body = packet.serialize().unwrap();
+        assert_eq!(body[0], 7);
+        assert_eq!(&body[1..body.len() - 1], encoded);
+        assert_eq!(body[body.len() - 1], 9);
+    }
+}
@@ -0,0 +1,84 @@
+use chlorophyte_terraria_protocol::frame::TerrariaFrameDecoder;
+use chlorophyte_terraria_protocol::network_text::NetworkText;
+use chlorophyte_terraria_protocol::packet::{
+    ModInfo, S2CFatalError, S2CPacket, S2CSyncMods, WriteTerrariaPacket,
+};
+use chlorophyte_terraria_protocol::types::TerrariaTypesW;
+use chlorophyte_terraria_protocol::view::S2CPacketView;
+
+#[test]
+fn view_matches_owned_packet() {
+    let error = NetworkText::LocalizationKey(
+        "LegacyMultiplayer.4".to_string(),
+        vec![NetworkText::Literal("1.4.4.9".to_string())],
+    );
+    let mut stream = vec![];
+    stream
+        .write_terraria_packet(S2CFatalError {
+            error: error.clone(),
+        })
+        .unwrap();
+    stream
+        .write_terraria_packet(S2CSyncMods {
+            allow_vanilla_clients: true,
+            mods: vec![ModInfo {
+                name: "CalamityMod".to_string(),
+                version: "2.0.3.1".to_string(),
+                hash: [7; 20],
+                signed: false,
+                configs: vec![("Config".to_string(), "{}".to_string())],
+            }],
+        })
+        .unwrap();
+
+    let mut decoder = TerrariaFrameDecoder::new();
+    decoder.feed(&stream);
+    let Some(S2CPacketView::FatalError(view)) = decoder.next_view().unwrap() else {
+        panic!("expected a fatal error");
+    };
+    assert!(view.is_version_mismatch());
+    assert_eq!(view.into_owned().error, error);
+
+    let Some(S2CPacketView::SyncMods(view)) = decoder.next_view().unwrap() else {
+        panic!("expected a mod list");
+    };
+    assert!(view.allow_vanilla_clients);
+    let owned = view.mods[0].clone().into_owned();
+    assert_eq!(owned.name, "CalamityMod");
+    assert_eq!(owned.version, "2.0.3.1");
+    assert_eq!(owned.configs, [("Config".to_string(), "{}".to_string())]);
+    assert!(decoder.next_view().unwrap().is_none());
+}
+
+#[test]
+fn unknown_packets_are_left_as_bodies() {
+    let view = S2CPacketView::parse(200, &[1, 2, 3]).unwrap();
+    assert_eq!(view.id(), 200);
+    assert!(matches!(
+        view,
+        S2CPacketView::Other {
+            body: [1, 2, 3],
+            ..
+        }
+    ));
+    assert!(S2CPacket::deserialize(2, vec![0, 2, 0xC3, 0x28]).is_err());
+    assert!(S2CPacketView::parse(2, &[0, 2, 0xC3, 0x28]).is_err());
+}
+
+#[test]
+fn kick_view_is_read_without_copying() {
+    let error = NetworkText::Formattable(
+        "This server runs tModLoader v{0}".to_string(),
+        vec![NetworkText::Literal("2023.8.3.4".to_string())],
+    );
+    let mut body = vec![];
+    body.write_network_text(error.clone()).unwrap();
+    let S2CPacketView::FatalError(view) = S2CPacketView::parse(2, &body).unwrap() else {
+        panic!("expected a fatal error");
+    };
+    let owned = S2CFatalError { error };
+    assert_eq!(view.error.render(), owned.error.render());
+    assert!(view.is_tmodloader() && owned.is_tmodloader());
+    assert_eq!(view.tmodloader_version().as_deref(), Some("2023.8.3.4"));
+    assert_eq!(view.tmodloader_version(), owned.tmodloader_version());
+}
@@ -0,0 +1,67 @@
+//! The fixtures are hand-built world info frames in the 1.4.4.9 layout, not captures of a
+//! real server. They were written by this crate's encoder, so `round_trip` only checks that
+//! decoding and encoding agree, not that the layout matches the game
+
+use chlorophyte_terraria_protocol::packet::{
+    ReadTerrariaPacket, S2CPacket, S2CWorldInfo, TerrariaPacket,
+};
+use std::io::Cursor;
+
+const MEDIUM_CLASSIC: &[u8] = include_bytes!("fixtures/world_info_medium_classic.bin");
+const ZENITH_MASTER: &[u8] = include_bytes!("fixtures/world_info_zenith_master.bin");
+
+fn decode(frame: &[u8]) -> S2CWorldInfo {
+    match Cursor::new(frame.to_vec()).read_any_packet().unwrap() {
+        S2CPacket::WorldInfo(world_info) => world_info,
+        packet => panic!("Expected world info, got packet {}", packet.id()),
+    }
+}
+
+#[test]
+fn medium_classic() {
+    let w = decode(MEDIUM_CLASSIC);
+    assert_eq!(w.world_name, "Chlorophyte Test");
+    assert_eq!(w.world_id, 123_456_789);
+    assert_eq!((w.max_tiles_x, w.max_tiles_y), (6400, 1800));
+    assert_eq!((w.spawn_x, w.spawn_y), (3200, 400));
+    assert_eq!(w.game_mode, 0);
+    assert_eq!(w.time, 27000);
+    assert!(w.day_time && !w.blood_moon && !w.eclipse);
+    assert_eq!(w.moon_phase, 3);
+    assert!(w.flags.hardmode);
+    assert!(w.flags.downed_plantera);
+    assert!(w.flags.downed_moon_lord);
+    assert!(w.flags.downed_golem);
+    assert!(!w.flags.server_side_characters);
+    assert!(!w.flags.for_the_worthy);
+    assert!(!w.flags.zenith_world);
+    assert_eq!(w.ore_tiers, [7, 6, 9, 8, 107, 108, 111]);
+    assert_eq!(w.invasion_type, 0);
+    assert_eq!(w.lobby_id, 0);
+}
+
+#[test]
+fn zenith_master() {
+    let w = decode(ZENITH_MASTER);
+    assert_eq!(w.world_name, "Zenith Seed");
+    assert_eq!(w.world_id, -42);
+    assert_eq!(w.game_mode, 2);
+    assert!(!w.day_time && w.blood_moon);
+    assert!(w.flags.zenith_world);
+    assert!(w.flags.for_the_worthy);
+    assert!(w.flags.remix_world);
+    assert!(w.flags.not_the_bees_world);
+    assert!(w.flags.no_traps_world);
+    assert!(w.flags.drunk_world);
+    assert!(!w.flags.hardmode);
+    assert_eq!((w.sundial_cooldown, w.moondial_cooldown), (3, 1));
+    assert_eq!(w.invasion_type, 1);
+    assert!((w.sandstorm_severity - 0.5).abs() < f32::EPSILON);
+}
+
+#[test]
+fn round_trip() {
+    for frame in [MEDIUM_CLASSIC, ZENITH_MASTER] {
+        assert_eq!(decode(frame).serialize().unwrap(), frame[3..]);
+    }
+}
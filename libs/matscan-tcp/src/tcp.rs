@@ -197,13 +197,20 @@ impl StatelessTcpWriteHalf {
     }
 
     pub fn send_syn(&mut self, addr: SocketAddrV4, sequence: u32) {
+        let source_port = self.source_port.pick(sequence);
+        self.send_syn_from(addr, source_port, sequence);
+    }
+
+    /// Like `send_syn`, but from the given source port instead of one picked based on the
+    /// sequence number, so the sequence number can depend on the source port
+    pub fn send_syn_from(&mut self, addr: SocketAddrV4, source_port: u16, sequence: u32) {
         let packet = self.template_syn_packet.build(tcp_template::PacketRepr {
             dest_addr: *addr.ip(),
             dest_port: addr.port(),
             sequence,
             acknowledgement: 0,
             payload: &[],
-            source_port: self.source_port.pick(sequence),
+            source_port,
         });
 
         #[cfg(not(feature = "benchmark"))]